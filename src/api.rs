@@ -1,152 +1,288 @@
-use anyhow::Result;
-use reqwest::Client;
+use bytes::Bytes;
+use reqwest::{Client, Method, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashSet;
+use std::time::Duration;
 
 use crate::config::N8nConfig;
+pub use crate::error::ApiError;
+pub use crate::retry::RetryPolicy;
 use serde_json::json;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Workflow {
     pub id: String,
     pub name: String,
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub version_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct WorkflowList {
     data: Vec<Workflow>,
+    #[serde(default)]
+    next_cursor: Option<String>,
 }
 
-pub async fn list_workflows(config: &N8nConfig) -> Result<Vec<Workflow>> {
-    let client = Client::new();
-    let url = config.endpoint("workflows");
-
-    let resp = client
-        .get(url)
-        .header("X-N8N-API-KEY", &config.api_key)
-        .send()
-        .await?;
-
-    // Check for authentication errors first
-    if resp.status() == 401 {
-        return Err(anyhow::anyhow!(
-            "Authentication failed. Please check your N8N_API_KEY"
-        ));
-    }
-    if resp.status() == 404 {
-        return Err(anyhow::anyhow!(
-            "API endpoint not found. Please check your N8N_HOST URL"
-        ));
+/// Server-side filters accepted by the `/workflows` listing endpoint.
+#[derive(Debug, Default, Clone)]
+pub struct ListFilter {
+    pub active: Option<bool>,
+    pub tags: Vec<String>,
+    pub name: Option<String>,
+    pub project_id: Option<String>,
+}
+
+impl ListFilter {
+    fn apply(&self, url: &mut url::Url) {
+        let mut query = url.query_pairs_mut();
+        if let Some(active) = self.active {
+            query.append_pair("active", &active.to_string());
+        }
+        if !self.tags.is_empty() {
+            query.append_pair("tags", &self.tags.join(","));
+        }
+        if let Some(name) = &self.name {
+            query.append_pair("name", name);
+        }
+        if let Some(project_id) = &self.project_id {
+            query.append_pair("projectId", project_id);
+        }
     }
+}
+
+/// Default number of workflows requested per page.
+pub const DEFAULT_PAGE_LIMIT: u32 = 100;
 
-    let resp = resp.error_for_status()?;
+/// User-Agent sent with every request.
+pub const USER_AGENT: &str = concat!("n8n-workflow-sync/", env!("CARGO_PKG_VERSION"));
 
-    let list: WorkflowList = resp
-        .json()
-        .await
-        .map_err(|e| {
-            anyhow::anyhow!(
-                "Failed to parse response JSON: {}. This might indicate the n8n API format has changed or the server returned HTML instead of JSON.",
-                e
-            )
-        })?;
+/// Overall timeout applied to each request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
-    Ok(list.data)
+/// Build the HTTP client shared by every request the CLI makes.
+pub fn build_http_client() -> Result<Client, ApiError> {
+    Ok(Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(DEFAULT_TIMEOUT)
+        .connect_timeout(Duration::from_secs(10))
+        .build()?)
 }
 
-pub async fn create_workflow(config: &N8nConfig, name: &str) -> Result<Workflow> {
-    let client = Client::new();
-    let url = config.endpoint("workflows");
-    let body = json!({
-        "name": name,
-        "nodes": [],
-        "connections": {},
-        "settings": {}
-    });
-    
-    let resp = client
-        .post(url)
-        .header("X-N8N-API-KEY", &config.api_key)
-        .json(&body)
-        .send()
-        .await?;
-    
-    // Check for authentication errors first
-    if resp.status() == 401 {
-        return Err(anyhow::anyhow!("Authentication failed. Please check your N8N_API_KEY"));
-    }
-    if resp.status() == 404 {
-        return Err(anyhow::anyhow!("API endpoint not found. Please check your N8N_HOST URL"));
+/// Client for the n8n Public API, reusing one connection pool for all calls.
+#[derive(Debug, Clone)]
+pub struct N8nClient {
+    http: Client,
+    config: N8nConfig,
+    retry: RetryPolicy,
+}
+
+/// Lazily walks the paginated `/workflows` listing, following `nextCursor`.
+pub struct WorkflowPages<'a> {
+    client: &'a N8nClient,
+    filter: ListFilter,
+    limit: u32,
+    cursor: Option<String>,
+    /// Cursors already followed, to stop if the server hands one out again.
+    seen: HashSet<String>,
+    done: bool,
+}
+
+impl<'a> WorkflowPages<'a> {
+    /// Fetch the next page of workflows, or `None` once every page was returned.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<Workflow>>, ApiError> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let mut url = self.client.config.endpoint("workflows");
+        self.filter.apply(&mut url);
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("limit", &self.limit.to_string());
+            if let Some(cursor) = &self.cursor {
+                query.append_pair("cursor", cursor);
+            }
+        }
+
+        let bytes = self
+            .client
+            .send_idempotent(self.client.request(Method::GET, url), None)
+            .await?;
+        let list: WorkflowList = serde_json::from_slice(&bytes)?;
+
+        self.cursor = list.next_cursor.filter(|c| !c.is_empty());
+        if let Some(cursor) = &self.cursor
+            && !self.seen.insert(cursor.clone())
+        {
+            self.done = true;
+            return Err(ApiError::RepeatedCursor {
+                cursor: cursor.clone(),
+            });
+        }
+        self.done = self.cursor.is_none();
+        Ok(Some(list.data))
     }
-    
-    let resp = resp.error_for_status()?;
-    let wf: Workflow = resp.json().await?;
-    Ok(wf)
 }
 
-/// Fetch a workflow by id, returning the raw JSON representation
-pub async fn get_workflow(config: &N8nConfig, id: &str) -> Result<Value> {
-    let client = Client::new();
-    let url = config.endpoint(&format!("workflows/{}", id));
-    
-    let resp = client
-        .get(url)
-        .header("X-N8N-API-KEY", &config.api_key)
-        .send()
-        .await?;
-    
-    // Check for authentication errors first
-    if resp.status() == 401 {
-        return Err(anyhow::anyhow!("Authentication failed. Please check your N8N_API_KEY"));
+impl N8nClient {
+    /// Create a client using the default HTTP settings.
+    pub fn new(config: N8nConfig) -> Result<Self, ApiError> {
+        Ok(Self::with_http_client(config, build_http_client()?))
+    }
+
+    /// Create a client around an already configured `reqwest::Client`.
+    pub fn with_http_client(config: N8nConfig, http: Client) -> Self {
+        Self {
+            http,
+            config,
+            retry: RetryPolicy::default(),
+        }
     }
-    if resp.status() == 404 {
-        return Err(anyhow::anyhow!("Workflow with ID {} not found", id));
+
+    /// Replace the retry policy used for idempotent requests.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
     }
-    
-    let resp = resp.error_for_status()?;
-    Ok(resp.json().await?)
-}
 
-/// Update an existing workflow with the provided JSON body
-pub async fn update_workflow(
-    config: &N8nConfig,
-    id: &str,
-    data: &Value,
-) -> Result<Workflow> {
-    let client = Client::new();
-    let url = config.endpoint(&format!("workflows/{}", id));
-
-    let resp = client
-        .put(url)
-        .header("X-N8N-API-KEY", &config.api_key)
-        .json(data)
-        .send()
-        .await?;
-
-    let status = resp.status();
-    let bytes = resp.bytes().await?;
-
-    // Check for authentication errors first
-    if status == 401 {
-        return Err(anyhow::anyhow!(
-            "Authentication failed. Please check your N8N_API_KEY"
-        ));
+    pub fn config(&self) -> &N8nConfig {
+        &self.config
     }
-    if status == 404 {
-        return Err(anyhow::anyhow!("Workflow with ID {} not found", id));
+
+    /// The underlying HTTP client, for requests to other services.
+    pub fn http(&self) -> &Client {
+        &self.http
+    }
+
+    fn request(&self, method: Method, url: url::Url) -> RequestBuilder {
+        self.http
+            .request(method, url)
+            .header("X-N8N-API-KEY", &self.config.api_key)
+    }
+
+    /// Page through workflows matching `filter`, fetching lazily.
+    pub fn workflow_pages(&self, filter: ListFilter, limit: u32) -> WorkflowPages<'_> {
+        WorkflowPages {
+            client: self,
+            filter,
+            limit,
+            cursor: None,
+            seen: HashSet::new(),
+            done: false,
+        }
+    }
+
+    /// Fetch every workflow matching `filter`, following pagination until the last page.
+    pub async fn list_workflows(
+        &self,
+        filter: &ListFilter,
+        limit: u32,
+    ) -> Result<Vec<Workflow>, ApiError> {
+        let mut pages = self.workflow_pages(filter.clone(), limit);
+        let mut workflows = Vec::new();
+        while let Some(page) = pages.next_page().await? {
+            workflows.extend(page);
+        }
+        Ok(workflows)
     }
 
-    if !status.is_success() {
-        // Attempt to extract a useful message from the response body
-        if let Ok(json) = serde_json::from_slice::<Value>(&bytes) {
-            if let Some(msg) = json.get("message").and_then(|v| v.as_str()) {
-                return Err(anyhow::anyhow!("HTTP {}: {}", status, msg));
+    /// Send a request, turning unsuccessful responses into an [`ApiError`].
+    ///
+    /// `id` names the workflow the request targets so a 404 can be reported
+    /// as a missing workflow rather than a wrong base URL.
+    async fn send(&self, req: RequestBuilder, id: Option<&str>) -> Result<Bytes, ApiError> {
+        let resp = req.send().await?;
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let bytes = resp.bytes().await?;
+
+        if !status.is_success() {
+            return Err(ApiError::from_response(status, &headers, &bytes, id));
+        }
+        Ok(bytes)
+    }
+
+    /// Like [`send`](Self::send), but retries transient failures according to
+    /// the client's [`RetryPolicy`]. Only use for requests that are safe to repeat.
+    async fn send_idempotent(
+        &self,
+        req: RequestBuilder,
+        id: Option<&str>,
+    ) -> Result<Bytes, ApiError> {
+        let mut attempt = 0;
+        loop {
+            let this_try = req
+                .try_clone()
+                .expect("API requests never use streaming bodies");
+            match self.send(this_try, id).await {
+                Err(err) if attempt < self.retry.max_retries && err.is_transient() => {
+                    let Some(delay) = self.retry.delay(attempt, err.retry_after()) else {
+                        return Err(err);
+                    };
+                    attempt += 1;
+                    tracing::warn!(
+                        attempt,
+                        max_retries = self.retry.max_retries,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %err,
+                        "transient n8n API failure, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
             }
         }
-        let body = String::from_utf8_lossy(&bytes);
-        return Err(anyhow::anyhow!("HTTP {}: {}", status, body));
     }
 
-    let wf: Workflow = serde_json::from_slice(&bytes)?;
-    Ok(wf)
+    pub async fn create_workflow(&self, name: &str) -> Result<Workflow, ApiError> {
+        let url = self.config.endpoint("workflows");
+        let body = json!({
+            "name": name,
+            "nodes": [],
+            "connections": {},
+            "settings": {}
+        });
+
+        let bytes = self
+            .send(self.request(Method::POST, url).json(&body), None)
+            .await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Fetch a workflow by id, returning the raw JSON representation
+    pub async fn get_workflow(&self, id: &str) -> Result<Value, ApiError> {
+        let url = self.config.endpoint(&format!("workflows/{}", id));
+
+        let bytes = self
+            .send_idempotent(self.request(Method::GET, url), Some(id))
+            .await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Update an existing workflow with the provided JSON body
+    pub async fn update_workflow(&self, id: &str, data: &Value) -> Result<Workflow, ApiError> {
+        let url = self.config.endpoint(&format!("workflows/{}", id));
+
+        let bytes = self
+            .send_idempotent(self.request(Method::PUT, url).json(data), Some(id))
+            .await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
 }
@@ -1,27 +1,434 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
+use std::path::{Path, PathBuf};
 use url::Url;
 
-#[derive(Debug, Clone)]
+use crate::layout::Layout;
+use crate::nodes::NodeSource;
+use crate::secrets::{self, DefaultStore, SecretStore};
+use crate::workflow::Format;
+
+/// Application name used for the platform config directory.
+pub const APP_NAME: &str = "n8n-workflow-sync";
+const CONFIG_NAME: &str = "config";
+
+/// Environment variable overriding the location of the config file.
+pub const CONFIG_PATH_ENV: &str = "N8N_SYNC_CONFIG";
+
+/// Keys accepted by `config get` and `config set`.
+pub const CONFIG_KEYS: &[&str] = &[
+    "host",
+    "api_key_command",
+    "commit_message",
+    "node_source",
+    "default_profile",
+];
+
+/// Environment variable naming a file that contains the API key.
+pub const API_KEY_FILE_ENV: &str = "N8N_API_KEY_FILE";
+
+/// Name of the profile stored at the top level of the config file.
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Clone)]
 pub struct N8nConfig {
     pub api_key: String,
     pub host: Url,
 }
 
+impl std::fmt::Debug for N8nConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("N8nConfig")
+            .field("api_key", &redact(&self.api_key))
+            .field("host", &self.host.as_str())
+            .finish()
+    }
+}
+
+/// Connection settings for one n8n instance.
+///
+/// The API key itself lives in a [`SecretStore`] or is produced by
+/// `api_key_command`; `api_key` is only read for config files written before
+/// secret storage existed.
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub host: Option<String>,
+    /// Shell command printing the API key, e.g. `pass show n8n/api-key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_command: Option<String>,
+    /// Template for commits of a single workflow, see [`crate::git::commit_message`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
+    /// Where node versions are read from, see [`crate::nodes::NodeSource`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
+
+impl std::fmt::Debug for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Profile")
+            .field("host", &self.host)
+            .field("api_key_command", &self.api_key_command)
+            .field("commit_message", &self.commit_message)
+            .field("node_source", &self.node_source)
+            .field("api_key", &self.api_key.as_deref().map(redact))
+            .finish()
+    }
+}
+
+impl Profile {
+    pub fn get(&self, key: &str) -> anyhow::Result<Option<&str>> {
+        Ok(match key {
+            "host" => self.host.as_deref(),
+            "api_key_command" => self.api_key_command.as_deref(),
+            "commit_message" => self.commit_message.as_deref(),
+            "node_source" => self.node_source.as_deref(),
+            "api_key" => return Err(api_key_not_in_file()),
+            _ => return Err(unknown_key(key)),
+        })
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        match key {
+            "host" => {
+                normalize_host(value)?;
+                self.host = Some(value.to_string());
+            }
+            "api_key_command" => self.api_key_command = Some(value.to_string()),
+            "commit_message" => self.commit_message = Some(value.to_string()),
+            "node_source" => {
+                value.parse::<NodeSource>()?;
+                self.node_source = Some(value.to_string());
+            }
+            "api_key" => return Err(api_key_not_in_file()),
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+}
+
+fn api_key_not_in_file() -> anyhow::Error {
+    anyhow::anyhow!(
+        "The API key is kept in the system keyring, not the config file. Use `config set-key` to change it"
+    )
+}
+
+/// Where the API key in use was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    Env,
+    EnvFile,
+    Command,
+    SecretStore,
+    ConfigFile,
+}
+
+impl KeySource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeySource::Env => "env",
+            KeySource::EnvFile => "key file",
+            KeySource::Command => "command",
+            KeySource::SecretStore => "secret store",
+            KeySource::ConfigFile => "file",
+        }
+    }
+}
+
+/// Find the API key for a profile.
+///
+/// In order of precedence: `N8N_API_KEY`, the file named by
+/// `N8N_API_KEY_FILE`, the profile's `api_key_command`, the secret store and
+/// finally a legacy plain-text `api_key` in the config file. The environment
+/// is only consulted when `use_env` is set, see [`N8nConfig::resolve`].
+pub fn resolve_api_key(
+    name: &str,
+    profile: &Profile,
+    secrets: &dyn SecretStore,
+    use_env: bool,
+) -> anyhow::Result<Option<(String, KeySource)>> {
+    if use_env {
+        if let Ok(key) = env::var("N8N_API_KEY") {
+            return Ok(Some((key, KeySource::Env)));
+        }
+        if let Some(path) = env::var_os(API_KEY_FILE_ENV) {
+            let key = secrets::read_key_file(Path::new(&path))
+                .with_context(|| format!("Failed to read {}", API_KEY_FILE_ENV))?;
+            return Ok(Some((key, KeySource::EnvFile)));
+        }
+    }
+    if let Some(cmd) = &profile.api_key_command {
+        let key = secrets::run_key_command(cmd)
+            .with_context(|| format!("api_key_command for profile \"{}\" failed", name))?;
+        return Ok(Some((key, KeySource::Command)));
+    }
+    if let Some(key) = secrets.get(name)? {
+        return Ok(Some((key, KeySource::SecretStore)));
+    }
+    Ok(profile
+        .api_key
+        .clone()
+        .map(|key| (key, KeySource::ConfigFile)))
+}
+
+/// Settings persisted in the platform config directory via `confy`.
+///
+/// Top-level settings form the `default` profile; other instances
+/// live under `[profiles.<name>]`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigFile {
+    #[serde(flatten)]
+    pub default: Profile,
+    /// Profile used when neither `--profile` nor `N8N_PROFILE` is given.
+    pub default_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ConfigFile {
+    /// Location of the config file, honouring `N8N_SYNC_CONFIG`.
+    pub fn path() -> anyhow::Result<PathBuf> {
+        if let Ok(p) = env::var(CONFIG_PATH_ENV) {
+            return Ok(PathBuf::from(p));
+        }
+        Ok(confy::get_configuration_file_path(APP_NAME, CONFIG_NAME)?)
+    }
+
+    /// Load the config file, returning defaults if it does not exist yet.
+    pub fn load() -> anyhow::Result<Self> {
+        Self::load_from(Self::path()?)
+    }
+
+    pub fn load_from<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        confy::load_path(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))
+    }
+
+    pub fn store(&self) -> anyhow::Result<PathBuf> {
+        let path = Self::path()?;
+        self.store_to(&path)?;
+        Ok(path)
+    }
+
+    pub fn store_to<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        confy::store_path(path, self)
+            .with_context(|| format!("Failed to write config file {}", path.display()))
+    }
+
+    /// Name of the profile to use: an explicit request wins, then the
+    /// stored `default_profile`, then the top-level `default` profile.
+    pub fn active_profile(&self, requested: Option<&str>) -> String {
+        requested
+            .or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string()
+    }
+
+    /// Names of all configured profiles, starting with `default`.
+    pub fn profile_names(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_PROFILE.to_string())
+            .chain(self.profiles.keys().filter(|k| *k != DEFAULT_PROFILE).cloned())
+            .collect()
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        if name == DEFAULT_PROFILE {
+            Some(&self.default)
+        } else {
+            self.profiles.get(name)
+        }
+    }
+
+    /// Mutable access to a profile, creating it if needed.
+    pub fn profile_mut(&mut self, name: &str) -> &mut Profile {
+        if name == DEFAULT_PROFILE {
+            &mut self.default
+        } else {
+            self.profiles.entry(name.to_string()).or_default()
+        }
+    }
+
+    pub fn get(&self, profile: &str, key: &str) -> anyhow::Result<Option<&str>> {
+        if key == "default_profile" {
+            return Ok(self.default_profile.as_deref());
+        }
+        match self.profile(profile) {
+            Some(p) => p.get(key),
+            None => Err(unknown_profile(profile)),
+        }
+    }
+
+    /// Commit message template for `profile`, falling back to the one set
+    /// at the top level and then to [`DEFAULT_COMMIT_MESSAGE`].
+    ///
+    /// [`DEFAULT_COMMIT_MESSAGE`]: crate::git::DEFAULT_COMMIT_MESSAGE
+    pub fn commit_message(&self, profile: &str) -> String {
+        self.profile(profile)
+            .and_then(|p| p.commit_message.clone())
+            .or_else(|| self.default.commit_message.clone())
+            .unwrap_or_else(|| crate::git::DEFAULT_COMMIT_MESSAGE.to_string())
+    }
+
+    /// Source of node versions for `profile`, falling back to the one set at
+    /// the top level and then to the connected instance.
+    pub fn node_source(&self, profile: &str) -> anyhow::Result<NodeSource> {
+        self.profile(profile)
+            .and_then(|p| p.node_source.as_deref())
+            .or(self.default.node_source.as_deref())
+            .map_or(Ok(NodeSource::default()), str::parse)
+    }
+
+    pub fn set(&mut self, profile: &str, key: &str, value: &str) -> anyhow::Result<()> {
+        if key == "default_profile" {
+            if self.profile(value).is_none() {
+                return Err(unknown_profile(value));
+            }
+            self.default_profile = Some(value.to_string());
+            return Ok(());
+        }
+        self.profile_mut(profile).set(key, value)
+    }
+}
+
+fn unknown_profile(name: &str) -> anyhow::Error {
+    anyhow::anyhow!("Profile \"{}\" is not defined in the config file", name)
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Unknown config key \"{}\". Valid keys: {}",
+        key,
+        CONFIG_KEYS.join(", ")
+    )
+}
+
+/// Name of the file holding per-project settings.
+pub const PROJECT_CONFIG_FILE: &str = ".n8n-sync.toml";
+
+/// Per-project settings, read from a `.n8n-sync.toml` file in the workflow
+/// directory or one of its parents (up to the root of the git repository).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    /// File format for newly pulled workflows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    /// Layout for newly pulled workflows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+}
+
+impl ProjectConfig {
+    /// Settings that apply to `dir`, which does not need to exist yet.
+    pub fn find(dir: &Path) -> anyhow::Result<Self> {
+        let dir = std::path::absolute(dir)?;
+        for ancestor in dir.ancestors() {
+            let path = ancestor.join(PROJECT_CONFIG_FILE);
+            if path.is_file() {
+                return confy::load_path(&path)
+                    .with_context(|| format!("Failed to read project settings {}", path.display()));
+            }
+            if ancestor.join(".git").exists() {
+                break;
+            }
+        }
+        Ok(Self::default())
+    }
+}
+
+/// Mask a secret for display, keeping only its last four characters.
+pub fn redact(secret: &str) -> String {
+    let count = secret.chars().count();
+    if count <= 8 {
+        return "********".to_string();
+    }
+    let tail: String = secret.chars().skip(count - 4).collect();
+    format!("********{}", tail)
+}
+
+/// Turn a user-supplied host into the instance base URL, dropping any API suffix.
+pub fn normalize_host(host: &str) -> anyhow::Result<Url> {
+    let mut host = host.trim_end_matches('/').to_string();
+    if host.ends_with("/api/v1") {
+        host = host.trim_end_matches("/api/v1").to_string();
+    } else if host.ends_with("/v1") {
+        host = host.trim_end_matches("/v1").to_string();
+    }
+    host = format!("{}/", host);
+    Ok(Url::parse(&host)?)
+}
+
 impl N8nConfig {
     pub fn from_env() -> anyhow::Result<Self> {
         let api_key = env::var("N8N_API_KEY")?;
-        let mut host = env::var("N8N_HOST")?;
-        host = host.trim_end_matches('/').to_string();
-        if host.ends_with("/api/v1") {
-            host = host.trim_end_matches("/api/v1").to_string();
-        } else if host.ends_with("/v1") {
-            host = host.trim_end_matches("/v1").to_string();
+        let host = normalize_host(&env::var("N8N_HOST")?)?;
+        Ok(Self { api_key, host })
+    }
+
+    /// Build the configuration from a profile in the config file and the
+    /// API key held in `secrets`.
+    ///
+    /// `requested` is the profile named with `--profile` or `N8N_PROFILE`.
+    /// Without one, the file's default profile is used and `N8N_HOST`,
+    /// `N8N_API_KEY` and `N8N_API_KEY_FILE` take precedence over the stored
+    /// values. An explicitly selected profile ignores them, so variables left
+    /// over from another instance cannot redirect it.
+    pub fn resolve(
+        file: &ConfigFile,
+        requested: Option<&str>,
+        secrets: &dyn SecretStore,
+    ) -> anyhow::Result<Self> {
+        let profile = &file.active_profile(requested);
+        let use_env = requested.is_none();
+        let stored = match file.profile(profile) {
+            Some(p) => p.clone(),
+            None if profile == DEFAULT_PROFILE => Profile::default(),
+            None => return Err(unknown_profile(profile)),
+        };
+        if !use_env && env::var_os("N8N_HOST").is_some() {
+            tracing::warn!(profile, "ignoring N8N_HOST because a profile was selected explicitly");
         }
-        host = format!("{}/", host);
-        let host = Url::parse(&host)?;
+        let host = env::var("N8N_HOST")
+            .ok()
+            .filter(|_| use_env)
+            .or_else(|| stored.host.clone())
+            .ok_or_else(|| anyhow::anyhow!("No n8n host configured for profile \"{}\"", profile))?;
+        let api_key = match resolve_api_key(profile, &stored, secrets, use_env)? {
+            Some((key, KeySource::ConfigFile)) => {
+                tracing::warn!(
+                    profile,
+                    "API key read from plain-text config file; run `config set-key` to move it to the keyring"
+                );
+                key
+            }
+            Some((key, _)) => key,
+            None => {
+                return Err(anyhow::anyhow!(
+                    "No n8n API key configured for profile \"{}\"",
+                    profile
+                ));
+            }
+        };
+        let host = normalize_host(&host)?;
         Ok(Self { api_key, host })
     }
 
+    /// Load the config file and apply environment overrides.
+    ///
+    /// `profile` selects a named profile; `None` uses the file's default.
+    pub fn load(profile: Option<&str>) -> anyhow::Result<Self> {
+        let file = ConfigFile::load()?;
+        Self::resolve(&file, profile, &DefaultStore::from_env()?)
+    }
+
     pub fn endpoint(&self, path: &str) -> Url {
         self.host
             .join(&format!("api/v1/{}", path.trim_start_matches('/')))
@@ -32,6 +439,7 @@ impl N8nConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::secrets::MemoryStore;
     use serial_test::serial;
     use temp_env::with_vars;
 
@@ -67,4 +475,208 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    #[serial]
+    fn env_overrides_config_file() {
+        let file = ConfigFile {
+            default: Profile {
+                host: Some("http://from-file".into()),
+                api_key: Some("file-key".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        with_vars(
+            [
+                ("N8N_API_KEY", None),
+                ("N8N_API_KEY_FILE", None),
+                ("N8N_HOST", Some("http://from-env/api/v1")),
+            ],
+            || {
+                let cfg = N8nConfig::resolve(&file, None, &MemoryStore::default()).unwrap();
+                assert_eq!(cfg.host.as_str(), "http://from-env/");
+                assert_eq!(cfg.api_key, "file-key");
+            },
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn explicit_profile_ignores_env_overrides() {
+        let mut file = ConfigFile::default();
+        file.set(DEFAULT_PROFILE, "host", "http://staging").unwrap();
+        file.set("prod", "host", "http://prod").unwrap();
+        let secrets = MemoryStore::default();
+        secrets.set("prod", "prod-key").unwrap();
+
+        with_vars(
+            [
+                ("N8N_API_KEY", Some("staging-key")),
+                ("N8N_API_KEY_FILE", None),
+                ("N8N_HOST", Some("http://staging")),
+            ],
+            || {
+                let prod = N8nConfig::resolve(&file, Some("prod"), &secrets).unwrap();
+                assert_eq!(prod.host.as_str(), "http://prod/");
+                assert_eq!(prod.api_key, "prod-key");
+
+                let implicit = N8nConfig::resolve(&file, None, &secrets).unwrap();
+                assert_eq!(implicit.host.as_str(), "http://staging/");
+                assert_eq!(implicit.api_key, "staging-key");
+            },
+        );
+    }
+
+    #[test]
+    fn config_file_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        assert_eq!(ConfigFile::load_from(&path).unwrap(), ConfigFile::default());
+
+        let mut file = ConfigFile::default();
+        file.set(DEFAULT_PROFILE, "host", "https://n8n.example.com").unwrap();
+        assert!(file.set(DEFAULT_PROFILE, "hots", "x").is_err());
+        assert!(file.set(DEFAULT_PROFILE, "host", "not a url").is_err());
+        file.store_to(&path).unwrap();
+
+        let loaded = ConfigFile::load_from(&path).unwrap();
+        assert_eq!(
+            loaded.get(DEFAULT_PROFILE, "host").unwrap(),
+            Some("https://n8n.example.com")
+        );
+        assert!(loaded.get(DEFAULT_PROFILE, "api_key").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn named_profiles_are_stored_and_selected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let mut file = ConfigFile::default();
+        file.set(DEFAULT_PROFILE, "host", "http://dev").unwrap();
+        file.set("prod", "host", "http://prod").unwrap();
+        assert!(file.set("prod", "api_key", "prod-key").is_err());
+        let secrets = MemoryStore::default();
+        secrets.set(DEFAULT_PROFILE, "dev-key").unwrap();
+        secrets.set("prod", "prod-key").unwrap();
+        assert!(file.set(DEFAULT_PROFILE, "default_profile", "missing").is_err());
+        file.set(DEFAULT_PROFILE, "default_profile", "prod").unwrap();
+        file.store_to(&path).unwrap();
+
+        let loaded = ConfigFile::load_from(&path).unwrap();
+        assert_eq!(loaded, file);
+        assert_eq!(loaded.profile_names(), ["default", "prod"]);
+        assert_eq!(loaded.active_profile(None), "prod");
+        assert_eq!(loaded.active_profile(Some("default")), "default");
+        assert_eq!(loaded.commit_message("prod"), crate::git::DEFAULT_COMMIT_MESSAGE);
+        let mut custom = loaded.clone();
+        custom.set(DEFAULT_PROFILE, "commit_message", "sync {name}").unwrap();
+        assert_eq!(custom.commit_message("prod"), "sync {name}");
+        assert_eq!(custom.node_source("prod").unwrap(), NodeSource::Instance);
+        assert!(custom.set("prod", "node_source", "svn").is_err());
+        custom.set("prod", "node_source", "tag:n8n@1.64.0").unwrap();
+        assert_eq!(
+            custom.node_source("prod").unwrap(),
+            NodeSource::Tag(Some("n8n@1.64.0".into()))
+        );
+
+        with_vars(
+            [
+                ("N8N_API_KEY", None::<&str>),
+                ("N8N_API_KEY_FILE", None),
+                ("N8N_HOST", None),
+            ],
+            || {
+                let prod = N8nConfig::resolve(&loaded, None, &secrets).unwrap();
+                assert_eq!(prod.host.as_str(), "http://prod/");
+                assert_eq!(prod.api_key, "prod-key");
+                let dev = N8nConfig::resolve(&loaded, Some("default"), &secrets).unwrap();
+                assert_eq!(dev.api_key, "dev-key");
+                assert!(N8nConfig::resolve(&loaded, Some("staging"), &secrets).is_err());
+            },
+        );
+    }
+
+    #[test]
+    fn debug_output_redacts_api_key() {
+        let cfg = N8nConfig {
+            api_key: "n8n_api_0123456789abcdef".into(),
+            host: Url::parse("http://localhost/").unwrap(),
+        };
+        let debug = format!("{:?}", cfg);
+        assert!(!debug.contains("0123456789"));
+        assert!(debug.contains("cdef"));
+    }
+
+    #[test]
+    #[serial]
+    fn api_key_file_and_command_take_precedence_over_secret_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("api-key");
+        std::fs::write(&key_file, "from-file\n").unwrap();
+
+        let secrets = MemoryStore::default();
+        secrets.set(DEFAULT_PROFILE, "from-store").unwrap();
+        let mut profile = Profile::default();
+
+        with_vars(
+            [
+                ("N8N_API_KEY", None::<&str>),
+                ("N8N_API_KEY_FILE", Some(key_file.to_str().unwrap())),
+            ],
+            || {
+                let (key, source) = resolve_api_key(DEFAULT_PROFILE, &profile, &secrets, true)
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(key, "from-file");
+                assert_eq!(source, KeySource::EnvFile);
+            },
+        );
+
+        with_vars(
+            [("N8N_API_KEY", None::<&str>), ("N8N_API_KEY_FILE", None)],
+            || {
+                let (_, source) = resolve_api_key(DEFAULT_PROFILE, &profile, &secrets, true)
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(source, KeySource::SecretStore);
+
+                profile.api_key_command = Some("echo from-command".into());
+                let (key, source) = resolve_api_key(DEFAULT_PROFILE, &profile, &secrets, true)
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(key, "from-command");
+                assert_eq!(source, KeySource::Command);
+            },
+        );
+    }
+
+    #[test]
+    fn project_settings_apply_within_the_repository() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = root.path().join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::write(
+            root.path().join(PROJECT_CONFIG_FILE),
+            "format = \"json\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            repo.join(PROJECT_CONFIG_FILE),
+            "format = \"yaml\"\nlayout = \"expanded\"\n",
+        )
+        .unwrap();
+
+        let settings = ProjectConfig::find(&repo.join("flows/not-created-yet")).unwrap();
+        assert_eq!(settings.format, Some(Format::Yaml));
+        assert_eq!(settings.layout, Some(Layout::Expanded));
+
+        std::fs::remove_file(repo.join(PROJECT_CONFIG_FILE)).unwrap();
+        assert_eq!(
+            ProjectConfig::find(&repo.join("flows")).unwrap(),
+            ProjectConfig::default()
+        );
+    }
 }
@@ -0,0 +1,445 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::workflow::{find_node, node_key, node_label};
+
+/// A single value that differs between two versions of a workflow.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    /// Location of the value, e.g. `nodes["HTTP Request"].parameters.url`.
+    pub path: String,
+    /// Value in the old version, `None` if it was added.
+    pub old: Option<Value>,
+    /// Value in the new version, `None` if it was removed.
+    pub new: Option<Value>,
+}
+
+/// List the values that differ between `old` and `new`.
+///
+/// Objects are compared key by key and nodes are matched by ID, so a node
+/// edit is reported as changes to its fields rather than as a replaced
+/// array element.
+pub fn changes(old: &Value, new: &Value) -> Vec<Change> {
+    let mut out = Vec::new();
+    walk("", Some(old), Some(new), &mut out);
+    out
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn walk(path: &str, old: Option<&Value>, new: Option<&Value>, out: &mut Vec<Change>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Some(Value::Object(o)), Some(Value::Object(n))) => {
+            for (key, value) in o {
+                walk(&child_path(path, key), Some(value), n.get(key), out);
+            }
+            for (key, value) in n.iter().filter(|(k, _)| !o.contains_key(*k)) {
+                walk(&child_path(path, key), None, Some(value), out);
+            }
+        }
+        (Some(Value::Array(o)), Some(Value::Array(n))) if path == "nodes" => {
+            for node in o {
+                let key = node_key(node);
+                walk(&node_label(node), Some(node), find_node(Some(n), key), out);
+            }
+            for node in n
+                .iter()
+                .filter(|n| find_node(Some(o), node_key(n)).is_none())
+            {
+                walk(&node_label(node), None, Some(node), out);
+            }
+        }
+        _ => out.push(Change {
+            path: path.to_string(),
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+    }
+}
+
+/// A node identified by name and type in a [`WorkflowDiff`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeRef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub node_type: Option<String>,
+}
+
+impl NodeRef {
+    fn new(node: &Value) -> Self {
+        Self {
+            name: node_name(node).to_string(),
+            node_type: node.get("type").and_then(Value::as_str).map(str::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Field changes inside one node, with paths relative to the node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeChanges {
+    pub node: String,
+    pub changes: Vec<Change>,
+}
+
+/// One edge of the `connections` graph.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection {
+    pub from: String,
+    /// Connection type, `main` for regular data flow.
+    pub kind: String,
+    pub output: usize,
+    pub to: String,
+    pub input: usize,
+}
+
+impl fmt::Display for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} → {}", self.from, self.to)?;
+        if self.kind != "main" || self.output != 0 || self.input != 0 {
+            write!(f, " ({} {}→{})", self.kind, self.output, self.input)?;
+        }
+        Ok(())
+    }
+}
+
+/// Node-aware summary of the differences between two workflows.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowDiff {
+    pub nodes_added: Vec<NodeRef>,
+    pub nodes_removed: Vec<NodeRef>,
+    pub nodes_renamed: Vec<Rename>,
+    pub nodes_changed: Vec<NodeChanges>,
+    pub connections_added: Vec<Connection>,
+    pub connections_removed: Vec<Connection>,
+    pub settings: Vec<Change>,
+    /// Changes to the remaining top-level fields, such as `name`.
+    pub other: Vec<Change>,
+}
+
+impl WorkflowDiff {
+    /// Compare two workflows, usually after [`sanitize_for_update`] was
+    /// applied to both.
+    ///
+    /// [`sanitize_for_update`]: crate::workflow::sanitize_for_update
+    pub fn between(old: &Value, new: &Value) -> Self {
+        let mut diff = WorkflowDiff::default();
+        let empty = Vec::new();
+        let old_nodes = old.get("nodes").and_then(Value::as_array).unwrap_or(&empty);
+        let new_nodes = new.get("nodes").and_then(Value::as_array).unwrap_or(&empty);
+
+        // Renamed nodes keep their ID; map old names so connections of a
+        // renamed node don't show up as removed and re-added.
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        for node in old_nodes {
+            match find_node(Some(new_nodes), node_key(node)) {
+                None => diff.nodes_removed.push(NodeRef::new(node)),
+                Some(other) => {
+                    let (from, to) = (node_name(node), node_name(other));
+                    if from != to {
+                        diff.nodes_renamed.push(Rename {
+                            from: from.to_string(),
+                            to: to.to_string(),
+                        });
+                        renamed.insert(from.to_string(), to.to_string());
+                    }
+                    let mut changes = Vec::new();
+                    walk(
+                        "",
+                        Some(&without_name(node)),
+                        Some(&without_name(other)),
+                        &mut changes,
+                    );
+                    if !changes.is_empty() {
+                        diff.nodes_changed.push(NodeChanges {
+                            node: to.to_string(),
+                            changes,
+                        });
+                    }
+                }
+            }
+        }
+        for node in new_nodes {
+            if find_node(Some(old_nodes), node_key(node)).is_none() {
+                diff.nodes_added.push(NodeRef::new(node));
+            }
+        }
+
+        let old_edges: BTreeSet<Connection> = connections(old, &renamed);
+        let new_edges: BTreeSet<Connection> = connections(new, &HashMap::new());
+        diff.connections_added = new_edges.difference(&old_edges).cloned().collect();
+        diff.connections_removed = old_edges.difference(&new_edges).cloned().collect();
+
+        walk(
+            "settings",
+            old.get("settings"),
+            new.get("settings"),
+            &mut diff.settings,
+        );
+        let rest = |v: &Value| -> Value {
+            let mut obj = v.as_object().cloned().unwrap_or_default();
+            for key in ["nodes", "connections", "settings"] {
+                obj.remove(key);
+            }
+            Value::Object(obj)
+        };
+        walk("", Some(&rest(old)), Some(&rest(new)), &mut diff.other);
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == WorkflowDiff::default()
+    }
+}
+
+fn node_name(node: &Value) -> &str {
+    node.get("name")
+        .and_then(Value::as_str)
+        .or_else(|| node_key(node))
+        .unwrap_or_default()
+}
+
+fn without_name(node: &Value) -> Value {
+    let mut node = node.clone();
+    if let Some(obj) = node.as_object_mut() {
+        obj.remove("name");
+    }
+    node
+}
+
+/// Flatten the `connections` object into a set of edges, renaming nodes
+/// through `renamed`.
+fn connections(workflow: &Value, renamed: &HashMap<String, String>) -> BTreeSet<Connection> {
+    let rename = |name: &str| {
+        renamed
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    };
+    let mut edges = BTreeSet::new();
+    let Some(sources) = workflow.get("connections").and_then(Value::as_object) else {
+        return edges;
+    };
+    for (from, kinds) in sources {
+        for (kind, outputs) in kinds.as_object().into_iter().flatten() {
+            for (output, targets) in outputs.as_array().into_iter().flatten().enumerate() {
+                for target in targets.as_array().into_iter().flatten() {
+                    let Some(to) = target.get("node").and_then(Value::as_str) else {
+                        continue;
+                    };
+                    edges.insert(Connection {
+                        from: rename(from),
+                        kind: kind.clone(),
+                        output,
+                        to: rename(to),
+                        input: target.get("index").and_then(Value::as_u64).unwrap_or(0) as usize,
+                    });
+                }
+            }
+        }
+    }
+    edges
+}
+
+/// One operation of an RFC 6902 JSON Patch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PatchOp {
+    pub op: &'static str,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// Build a JSON Patch that turns `old` into `new`.
+///
+/// Nodes are matched by ID like in [`changes`]; edits inside a node address
+/// it by its index in `old`, removed nodes are deleted from the highest index
+/// down and added nodes are appended, so the operations apply in order.
+pub fn json_patch(old: &Value, new: &Value) -> Vec<PatchOp> {
+    let mut ops = Vec::new();
+    patch_value("", old, new, &mut ops);
+    ops
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn patch_value(pointer: &str, old: &Value, new: &Value, ops: &mut Vec<PatchOp>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(o), Value::Object(n)) => patch_object(pointer, o, n, ops),
+        (Value::Array(o), Value::Array(n)) if pointer == "/nodes" => {
+            let mut removed = Vec::new();
+            for (i, node) in o.iter().enumerate() {
+                match find_node(Some(n), node_key(node)) {
+                    Some(other) => patch_value(&format!("{}/{}", pointer, i), node, other, ops),
+                    None => removed.push(i),
+                }
+            }
+            for i in removed.into_iter().rev() {
+                ops.push(PatchOp {
+                    op: "remove",
+                    path: format!("{}/{}", pointer, i),
+                    value: None,
+                });
+            }
+            for node in n
+                .iter()
+                .filter(|n| find_node(Some(o), node_key(n)).is_none())
+            {
+                ops.push(PatchOp {
+                    op: "add",
+                    path: format!("{}/-", pointer),
+                    value: Some(node.clone()),
+                });
+            }
+        }
+        _ => ops.push(PatchOp {
+            op: "replace",
+            path: pointer.to_string(),
+            value: Some(new.clone()),
+        }),
+    }
+}
+
+fn patch_object(
+    pointer: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    ops: &mut Vec<PatchOp>,
+) {
+    for (key, value) in old {
+        let path = format!("{}/{}", pointer, escape(key));
+        match new.get(key) {
+            Some(other) => patch_value(&path, value, other, ops),
+            None => ops.push(PatchOp {
+                op: "remove",
+                path,
+                value: None,
+            }),
+        }
+    }
+    for (key, value) in new.iter().filter(|(k, _)| !old.contains_key(*k)) {
+        ops.push(PatchOp {
+            op: "add",
+            path: format!("{}/{}", pointer, escape(key)),
+            value: Some(value.clone()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_field_level_changes_per_node() {
+        let old = json!({
+            "name": "Flow",
+            "nodes": [
+                {"id": "a", "name": "Webhook", "parameters": {"path": "in"}},
+                {"id": "b", "name": "Old", "parameters": {}}
+            ]
+        });
+        let new = json!({
+            "name": "Flow",
+            "nodes": [
+                {"id": "c", "name": "New", "parameters": {}},
+                {"id": "a", "name": "Webhook", "parameters": {"path": "hook"}}
+            ]
+        });
+
+        let paths: Vec<_> = changes(&old, &new).into_iter().map(|c| c.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "nodes[\"Webhook\"].parameters.path",
+                "nodes[\"Old\"]",
+                "nodes[\"New\"]"
+            ]
+        );
+    }
+
+    fn sample(url: &str, second: &str, timezone: &str) -> Value {
+        json!({
+            "name": "Flow",
+            "nodes": [
+                {"id": "a", "name": "Webhook", "type": "n8n-nodes-base.webhook", "parameters": {}},
+                {"id": "b", "name": second, "type": "n8n-nodes-base.httpRequest", "parameters": {"url": url}}
+            ],
+            "connections": {"Webhook": {"main": [[{"node": second, "type": "main", "index": 0}]]}},
+            "settings": {"timezone": timezone}
+        })
+    }
+
+    #[test]
+    fn summarises_nodes_connections_and_settings() {
+        let old = sample("x", "Fetch", "UTC");
+        let mut new = sample("y", "Download", "CET");
+        new["nodes"].as_array_mut().unwrap().push(
+            json!({"id": "c", "name": "Set", "type": "n8n-nodes-base.set", "parameters": {}}),
+        );
+        new["connections"]["Download"] =
+            json!({"main": [[{"node": "Set", "type": "main", "index": 0}]]});
+
+        let diff = WorkflowDiff::between(&old, &new);
+        assert_eq!(diff.nodes_added.len(), 1);
+        assert_eq!(diff.nodes_added[0].name, "Set");
+        assert!(diff.nodes_removed.is_empty());
+        assert_eq!(
+            diff.nodes_renamed,
+            [Rename {
+                from: "Fetch".into(),
+                to: "Download".into()
+            }]
+        );
+        assert_eq!(diff.nodes_changed.len(), 1);
+        assert_eq!(diff.nodes_changed[0].node, "Download");
+        assert_eq!(diff.nodes_changed[0].changes[0].path, "parameters.url");
+        // The renamed node's existing edge is not reported again.
+        assert_eq!(diff.connections_added.len(), 1);
+        assert_eq!(diff.connections_added[0].to_string(), "Download → Set");
+        assert!(diff.connections_removed.is_empty());
+        assert_eq!(diff.settings[0].path, "settings.timezone");
+        assert!(diff.other.is_empty());
+        assert!(WorkflowDiff::between(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn json_patch_addresses_nodes_by_index() {
+        let old = json!({"nodes": [{"id": "a", "v": 1}, {"id": "b"}, {"id": "c"}], "name/x": 1});
+        let new = json!({"nodes": [{"id": "c"}, {"id": "a", "v": 2}, {"id": "d"}]});
+        let ops = serde_json::to_value(json_patch(&old, &new)).unwrap();
+        assert_eq!(
+            ops,
+            json!([
+                {"op": "remove", "path": "/name~1x"},
+                {"op": "replace", "path": "/nodes/0/v", "value": 2},
+                {"op": "remove", "path": "/nodes/1"},
+                {"op": "add", "path": "/nodes/-", "value": {"id": "d"}}
+            ])
+        );
+    }
+}
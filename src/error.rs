@@ -0,0 +1,195 @@
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde_json::Value;
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+
+/// Errors returned by every call to the n8n Public API.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Authentication failed. Please check your N8N_API_KEY")]
+    Unauthorized,
+    #[error("Access denied. The API key is not allowed to perform this operation")]
+    Forbidden,
+    #[error("{}", match id {
+        Some(id) => format!("Workflow with ID {} not found", id),
+        None => "API endpoint not found. Please check your N8N_HOST URL".to_string(),
+    })]
+    NotFound { id: Option<String> },
+    #[error("Conflict: {message}")]
+    Conflict { message: String },
+    #[error("Validation failed: {message}")]
+    Validation { message: String },
+    #[error("Rate limited by the n8n server{}", match retry_after {
+        Some(d) => format!(", retry after {}s", d.as_secs()),
+        None => String::new(),
+    })]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("HTTP {status}: {message}")]
+    Server { status: StatusCode, message: String },
+    #[error("The n8n server returned the page cursor {cursor:?} twice; stopping instead of listing forever")]
+    RepeatedCursor { cursor: String },
+    #[error("Request to the n8n server failed: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error(
+        "Failed to parse response JSON: {0}. This might indicate the n8n API format has changed or the server returned HTML instead of JSON."
+    )]
+    Decode(#[from] serde_json::Error),
+}
+
+impl ApiError {
+    /// Whether the failure is likely temporary and worth retrying.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ApiError::RateLimited { .. } => true,
+            ApiError::Server { status, .. } => matches!(
+                *status,
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            ApiError::Transport(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
+
+    /// Delay requested by the server before trying again, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ApiError::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Map an unsuccessful response to the matching error variant.
+    ///
+    /// `id` is the workflow the request targeted, if any, and is used to
+    /// tell a missing workflow apart from a wrong API base URL.
+    pub fn from_response(
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+        id: Option<&str>,
+    ) -> Self {
+        let message = error_message(body);
+        match status {
+            StatusCode::UNAUTHORIZED => ApiError::Unauthorized,
+            StatusCode::FORBIDDEN => ApiError::Forbidden,
+            StatusCode::NOT_FOUND => ApiError::NotFound {
+                id: id.map(str::to_string),
+            },
+            StatusCode::CONFLICT => ApiError::Conflict { message },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                ApiError::Validation { message }
+            }
+            StatusCode::TOO_MANY_REQUESTS => ApiError::RateLimited {
+                retry_after: retry_after(headers),
+            },
+            _ => ApiError::Server { status, message },
+        }
+    }
+}
+
+/// Extract the `message` field n8n puts in error bodies, falling back to the raw text.
+fn error_message(body: &[u8]) -> String {
+    if let Ok(json) = serde_json::from_slice::<Value>(body)
+        && let Some(msg) = json.get("message").and_then(|v| v.as_str())
+    {
+        return msg.to_string();
+    }
+    String::from_utf8_lossy(body).into_owned()
+}
+
+/// Parse a `Retry-After` header given either in seconds or as an HTTP date.
+/// A date in the past means "retry now".
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_statuses_to_variants() {
+        let headers = HeaderMap::new();
+        assert!(matches!(
+            ApiError::from_response(StatusCode::UNAUTHORIZED, &headers, b"", None),
+            ApiError::Unauthorized
+        ));
+        assert!(matches!(
+            ApiError::from_response(StatusCode::NOT_FOUND, &headers, b"", Some("7")),
+            ApiError::NotFound { id: Some(ref id) } if id == "7"
+        ));
+        assert!(matches!(
+            ApiError::from_response(StatusCode::BAD_GATEWAY, &headers, b"bad gateway", None),
+            ApiError::Server { status: StatusCode::BAD_GATEWAY, .. }
+        ));
+    }
+
+    #[test]
+    fn only_gateway_errors_and_rate_limits_are_transient() {
+        let headers = HeaderMap::new();
+        for status in [
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::GATEWAY_TIMEOUT,
+            StatusCode::TOO_MANY_REQUESTS,
+        ] {
+            assert!(ApiError::from_response(status, &headers, b"", None).is_transient());
+        }
+        for status in [
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::NOT_FOUND,
+        ] {
+            assert!(!ApiError::from_response(status, &headers, b"", None).is_transient());
+        }
+    }
+
+    #[test]
+    fn validation_uses_body_message() {
+        let err = ApiError::from_response(
+            StatusCode::BAD_REQUEST,
+            &HeaderMap::new(),
+            br#"{"message":"request/body must have required property 'name'"}"#,
+            Some("1"),
+        );
+        assert_eq!(
+            err.to_string(),
+            "Validation failed: request/body must have required property 'name'"
+        );
+    }
+
+    #[test]
+    fn rate_limit_reads_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "12".parse().unwrap());
+        let err = ApiError::from_response(StatusCode::TOO_MANY_REQUESTS, &headers, b"", None);
+        assert!(matches!(
+            err,
+            ApiError::RateLimited { retry_after: Some(d) } if d == Duration::from_secs(12)
+        ));
+    }
+
+    #[test]
+    fn retry_after_accepts_http_dates() {
+        let mut headers = HeaderMap::new();
+        let later = SystemTime::now() + Duration::from_secs(120);
+        let value = httpdate::fmt_http_date(later);
+        headers.insert(RETRY_AFTER, value.parse().unwrap());
+        let wait = retry_after(&headers).unwrap();
+        assert!(wait > Duration::from_secs(100) && wait <= Duration::from_secs(120));
+
+        headers.insert(RETRY_AFTER, "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+}
@@ -0,0 +1,367 @@
+use anyhow::Context;
+use git2::{ErrorCode, IndexAddOption, Oid, Repository, Signature, Time};
+use serde_json::Value;
+use std::path::Path;
+
+/// Message used for commits of a single workflow unless the profile sets
+/// `commit_message`. See [`commit_message`] for the placeholders.
+pub const DEFAULT_COMMIT_MESSAGE: &str = "feat: sync from n8n (workflow {id})";
+
+/// Identity used when neither the workflow nor git config provide one.
+const FALLBACK_NAME: &str = "n8n-workflow-sync";
+const FALLBACK_EMAIL: &str = "n8n@localhost";
+
+/// Open the git repository at `dir`, initialising one if none exists.
+///
+/// Returns the repository and whether it was newly created.
+pub fn open_or_init(dir: &Path) -> anyhow::Result<(Repository, bool)> {
+    match Repository::open(dir) {
+        Ok(repo) => Ok((repo, false)),
+        Err(_) => {
+            let repo = Repository::init(dir).with_context(|| {
+                format!("Failed to initialize git repository in {}", dir.display())
+            })?;
+            Ok((repo, true))
+        }
+    }
+}
+
+/// Fill in a commit message template for `workflow`.
+///
+/// Supported placeholders are `{id}`, `{name}`, `{versionId}` and
+/// `{updatedAt}`; missing values are replaced by an empty string.
+pub fn commit_message(template: &str, workflow: &Value) -> String {
+    let field = |key: &str| {
+        workflow
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    };
+    template
+        .replace("{id}", field("id"))
+        .replace("{name}", field("name"))
+        .replace("{versionId}", field("versionId"))
+        .replace("{updatedAt}", field("updatedAt"))
+}
+
+/// The identity configured in git (`user.name`/`user.email`), or a fixed
+/// fallback when none is set.
+fn local_signature(repo: &Repository) -> anyhow::Result<Signature<'static>> {
+    match repo.signature() {
+        Ok(sig) => Ok(sig.to_owned()),
+        Err(_) => Ok(Signature::now(FALLBACK_NAME, FALLBACK_EMAIL)?),
+    }
+}
+
+/// Parse an RFC 3339 timestamp such as `2024-05-01T09:30:00.000Z` as
+/// returned by n8n.
+fn parse_timestamp(s: &str) -> Option<Time> {
+    let num = |range: std::ops::Range<usize>| s.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !matches!(s.get(10..11), Some("T" | "t" | " ")) {
+        return None;
+    }
+
+    let rest = s[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset_minutes = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.get(0..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let h: i64 = rest.get(1..3)?.parse().ok()?;
+            let m: i64 = rest.get(4..6)?.parse().ok()?;
+            sign * (h * 60 + m)
+        }
+    };
+
+    // Days since the Unix epoch for a proleptic Gregorian date.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let seconds = days * 86_400 + hour * 3_600 + minute * 60 + second - offset_minutes * 60;
+    Some(Time::new(seconds, offset_minutes as i32))
+}
+
+/// Name and email of the user owning `workflow`.
+///
+/// The public API does not report who last edited a workflow, but it lists
+/// the owning personal project, named like `Jane Doe <jane@example.com>`.
+fn workflow_owner(workflow: &Value) -> Option<(String, String)> {
+    let shared = workflow.get("shared")?.as_array()?;
+    let project = shared
+        .iter()
+        .find(|s| s.get("role").and_then(Value::as_str) == Some("workflow:owner"))?
+        .get("project")?;
+    let name = project.get("name")?.as_str()?;
+    let (name, email) = name.trim().strip_suffix('>')?.split_once('<')?;
+    Some((name.trim().to_string(), email.trim().to_string()))
+}
+
+/// Author for a commit recording `workflow`: its owner, else the local git
+/// identity, dated at the workflow's `updatedAt` when present.
+fn workflow_author(repo: &Repository, workflow: &Value) -> anyhow::Result<Signature<'static>> {
+    let local = local_signature(repo)?;
+    let (name, email) = match workflow_owner(workflow) {
+        Some(owner) => owner,
+        None => (
+            local.name().unwrap_or(FALLBACK_NAME).to_string(),
+            local.email().unwrap_or(FALLBACK_EMAIL).to_string(),
+        ),
+    };
+    let time = workflow
+        .get("updatedAt")
+        .and_then(Value::as_str)
+        .and_then(parse_timestamp)
+        .unwrap_or(local.when());
+    Ok(Signature::new(&name, &email, &time)?)
+}
+
+/// Stage `paths` (relative to the work tree) and commit them on top of HEAD.
+/// Directories are staged recursively and paths that no longer exist are
+/// removed from the index.
+///
+/// The committer is the local git identity. When the commit records a single
+/// workflow, pass it as `workflow` to make its owner and `updatedAt` the
+/// author; otherwise the committer is used as author too.
+///
+/// Returns `None` without committing when the resulting tree matches HEAD.
+pub fn commit_paths(
+    repo: &Repository,
+    paths: &[&Path],
+    message: &str,
+    workflow: Option<&Value>,
+) -> anyhow::Result<Option<Oid>> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot commit in a bare repository"))?;
+    let mut index = repo.index()?;
+    for path in paths {
+        let full = workdir.join(path);
+        if full.is_dir() {
+            index.add_all([*path], IndexAddOption::DEFAULT, None)?;
+            index.update_all([*path], None)?;
+        } else if full.exists() {
+            index.add_path(path)?;
+        } else {
+            index.remove_path(path)?;
+            index.remove_dir(path, 0)?;
+        }
+    }
+    index.write()?;
+    let tree_id = index.write_tree()?;
+
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        // A new repository, or HEAD naming a branch without commits yet.
+        Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => None,
+        Err(e) => return Err(e).context("Failed to read HEAD"),
+    };
+    if let Some(parent) = &parent
+        && parent.tree_id() == tree_id
+    {
+        return Ok(None);
+    }
+
+    let tree = repo.find_tree(tree_id)?;
+    let committer = local_signature(repo)?;
+    let author = match workflow {
+        Some(wf) => workflow_author(repo, wf)?,
+        None => committer.clone(),
+    };
+    let parents: Vec<_> = parent.iter().collect();
+    let oid = repo.commit(Some("HEAD"), &author, &committer, message, &tree, &parents)?;
+    Ok(Some(oid))
+}
+
+/// Commit `files` in the repository containing them, if there is one.
+///
+/// Unlike [`commit_paths`] the files may be given relative to the current
+/// directory. Returns `None` when the files are not inside a repository or
+/// nothing changed.
+pub fn commit_files(
+    files: &[&Path],
+    message: &str,
+    workflow: Option<&Value>,
+) -> anyhow::Result<Option<Oid>> {
+    let Some(first) = files.first() else {
+        return Ok(None);
+    };
+    let start = first
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let Ok(repo) = Repository::discover(start) else {
+        return Ok(None);
+    };
+    let Some(workdir) = repo.workdir() else {
+        return Ok(None);
+    };
+    let workdir = workdir.canonicalize()?;
+    let mut relative = Vec::with_capacity(files.len());
+    for file in files {
+        // Files may have been removed; resolve their directory instead.
+        let abs = match file.canonicalize() {
+            Ok(abs) => abs,
+            Err(_) => {
+                let parent = file
+                    .parent()
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                parent
+                    .canonicalize()
+                    .with_context(|| format!("Failed to resolve {}", file.display()))?
+                    .join(file.file_name().unwrap_or_default())
+            }
+        };
+        relative.push(abs.strip_prefix(&workdir)?.to_path_buf());
+    }
+    let paths: Vec<&Path> = relative.iter().map(|p| p.as_path()).collect();
+    commit_paths(&repo, &paths, message, workflow)
+}
+
+/// Contents of `file` as of HEAD in the repository containing it.
+///
+/// Returns `None` if the file is not in a repository or not committed yet.
+pub fn head_content(file: &Path) -> anyhow::Result<Option<Vec<u8>>> {
+    let start = file
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let Ok(repo) = Repository::discover(start) else {
+        return Ok(None);
+    };
+    let (Some(workdir), Ok(head)) = (repo.workdir(), repo.head()) else {
+        return Ok(None);
+    };
+    let rel = file.canonicalize()?;
+    let Ok(rel) = rel.strip_prefix(workdir.canonicalize()?) else {
+        return Ok(None);
+    };
+    let tree = head.peel_to_tree()?;
+    let Ok(entry) = tree.get_path(rel) else {
+        return Ok(None);
+    };
+    let blob = entry.to_object(&repo)?.peel_to_blob()?;
+    Ok(Some(blob.content().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn commits_only_changes_on_top_of_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, created) = open_or_init(dir.path()).unwrap();
+        assert!(created);
+        let file = Path::new("workflow.json");
+
+        fs::write(dir.path().join(file), "{}").unwrap();
+        let first = commit_paths(&repo, &[file], "first", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(commit_paths(&repo, &[file], "again", None).unwrap(), None);
+
+        fs::write(dir.path().join(file), r#"{"name":"x"}"#).unwrap();
+        let second = commit_paths(&repo, &[file], "second", None)
+            .unwrap()
+            .unwrap();
+        let commit = repo.find_commit(second).unwrap();
+        assert_eq!(commit.parent_ids().collect::<Vec<_>>(), [first]);
+        assert!(!open_or_init(dir.path()).unwrap().1);
+    }
+
+    #[test]
+    fn only_an_unborn_head_starts_a_new_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, _) = open_or_init(dir.path()).unwrap();
+        let file = Path::new("workflow.json");
+        fs::write(dir.path().join(file), "{}").unwrap();
+
+        repo.set_head("refs/heads/empty").unwrap();
+        let first = commit_paths(&repo, &[file], "first", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(repo.find_commit(first).unwrap().parent_count(), 0);
+
+        // A damaged branch must not be replaced by a new history.
+        fs::write(dir.path().join(".git/refs/heads/empty"), "not an id\n").unwrap();
+        fs::write(dir.path().join(file), r#"{"name":"x"}"#).unwrap();
+        let err = commit_paths(&repo, &[file], "second", None).unwrap_err();
+        assert!(err.to_string().contains("Failed to read HEAD"), "{:#}", err);
+    }
+
+    #[test]
+    fn stages_directories_and_removed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, _) = open_or_init(dir.path()).unwrap();
+        let nodes = Path::new("nodes");
+        fs::create_dir(dir.path().join(nodes)).unwrap();
+        fs::write(dir.path().join("nodes/A.json"), "{}").unwrap();
+        fs::write(dir.path().join("nodes/B.json"), "{}").unwrap();
+        let missing = Path::new("workflow.meta.json");
+        commit_paths(&repo, &[nodes, missing], "first", None).unwrap();
+
+        fs::remove_file(dir.path().join("nodes/B.json")).unwrap();
+        let oid = commit_paths(&repo, &[nodes], "second", None)
+            .unwrap()
+            .unwrap();
+        let tree = repo.find_commit(oid).unwrap().tree().unwrap();
+        assert!(tree.get_path(Path::new("nodes/A.json")).is_ok());
+        assert!(tree.get_path(Path::new("nodes/B.json")).is_err());
+
+        fs::remove_dir_all(dir.path().join(nodes)).unwrap();
+        let oid = commit_paths(&repo, &[nodes], "third", None)
+            .unwrap()
+            .unwrap();
+        let tree = repo.find_commit(oid).unwrap().tree().unwrap();
+        assert!(tree.get_path(nodes).is_err());
+    }
+
+    #[test]
+    fn parses_n8n_timestamps() {
+        let t = parse_timestamp("2024-05-01T09:30:00.000Z").unwrap();
+        assert_eq!((t.seconds(), t.offset_minutes()), (1_714_555_800, 0));
+        let t = parse_timestamp("2024-05-01T11:30:00+02:00").unwrap();
+        assert_eq!((t.seconds(), t.offset_minutes()), (1_714_555_800, 120));
+        assert!(parse_timestamp("yesterday").is_none());
+    }
+
+    #[test]
+    fn workflow_commits_use_owner_and_update_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, _) = open_or_init(dir.path()).unwrap();
+        fs::write(dir.path().join("workflow.json"), "{}").unwrap();
+        let wf = serde_json::json!({
+            "id": "7",
+            "name": "Billing",
+            "versionId": "abc",
+            "updatedAt": "2024-05-01T09:30:00.000Z",
+            "shared": [{
+                "role": "workflow:owner",
+                "project": {"name": "Jane Doe <jane@example.com>", "type": "personal"}
+            }]
+        });
+
+        let message = commit_message("sync {name} ({id}@{versionId})", &wf);
+        assert_eq!(message, "sync Billing (7@abc)");
+        let oid = commit_paths(&repo, &[Path::new("workflow.json")], &message, Some(&wf))
+            .unwrap()
+            .unwrap();
+        let commit = repo.find_commit(oid).unwrap();
+        assert_eq!(commit.author().name(), Some("Jane Doe"));
+        assert_eq!(commit.author().email(), Some("jane@example.com"));
+        assert_eq!(commit.author().when().seconds(), 1_714_555_800);
+        assert_eq!(commit.message(), Some("sync Billing (7@abc)"));
+    }
+}
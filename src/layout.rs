@@ -0,0 +1,669 @@
+use anyhow::Context;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::ProjectConfig;
+use crate::workflow::{
+    self, Format, canonicalize, is_workflow_file, metadata_path, split_metadata, write_if_changed,
+};
+
+/// Directory holding the node files of an expanded `workflow.json` or
+/// `workflow.yaml`.
+pub const NODES_DIR: &str = "nodes";
+
+/// Key of the object that replaces a parameter extracted to its own file.
+const FILE_REF: &str = "$file";
+
+/// Parameters extracted to native files in the expanded layout, as
+/// `(node type, parameter, file extension)`.
+const SOURCE_PARAMETERS: &[(&str, &str, &str)] = &[
+    ("n8n-nodes-base.code", "jsCode", "js"),
+    ("n8n-nodes-base.code", "pythonCode", "py"),
+    ("n8n-nodes-base.function", "functionCode", "js"),
+    ("n8n-nodes-base.functionItem", "functionCode", "js"),
+    ("n8n-nodes-base.postgres", "query", "sql"),
+    ("n8n-nodes-base.mySql", "query", "sql"),
+    ("n8n-nodes-base.microsoftSql", "query", "sql"),
+    ("n8n-nodes-base.snowflake", "query", "sql"),
+    ("n8n-nodes-base.questDb", "query", "sql"),
+    ("n8n-nodes-base.timescaleDb", "query", "sql"),
+    ("n8n-nodes-base.crateDb", "query", "sql"),
+    ("n8n-nodes-base.html", "html", "html"),
+    ("n8n-nodes-base.emailSend", "html", "html"),
+];
+
+/// How a workflow is laid out on disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// The whole workflow in a single JSON file
+    #[default]
+    Single,
+    /// One file per node under `nodes/`, with code, SQL and HTML parameters
+    /// in files of their own
+    Expanded,
+}
+
+impl Layout {
+    /// Layout of the existing workflow file at `path`, `None` if there is
+    /// no such file.
+    pub fn detect(path: &Path) -> Option<Layout> {
+        if !path.is_file() {
+            return None;
+        }
+        // Only the workflow file decides: a `nodes/` directory next to it
+        // may belong to the user.
+        let expanded = workflow::read(path)
+            .ok()
+            .and_then(|json| json.get("nodes").and_then(Value::as_array).cloned())
+            .is_some_and(|nodes| nodes.iter().any(Value::is_string));
+        Some(if expanded {
+            Layout::Expanded
+        } else {
+            Layout::Single
+        })
+    }
+}
+
+/// Directory holding the node files of the workflow file at `path`:
+/// `nodes/` for `workflow.json` and `workflow.yaml`, `<stem>.nodes/` for
+/// any other file.
+pub fn nodes_dir(path: &Path) -> PathBuf {
+    if is_workflow_file(path) {
+        return path.with_file_name(NODES_DIR);
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}", stem, NODES_DIR))
+}
+
+/// Every path making up the workflow stored at `path`, relative to the same
+/// directory as `path`: the workflow file, its metadata sidecar and its node
+/// directory (which may not exist).
+pub fn workflow_paths(path: &Path) -> Vec<PathBuf> {
+    vec![path.to_path_buf(), metadata_path(path), nodes_dir(path)]
+}
+
+/// Turn a node name into a file stem that is valid on every platform.
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let stem = stem.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if stem.is_empty() {
+        "node".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+/// A workflow split into the files of the expanded layout.
+#[derive(Debug, PartialEq)]
+pub struct Expanded {
+    /// The workflow with each node replaced by the path of its file.
+    pub workflow: Value,
+    /// File contents keyed by path relative to the workflow file.
+    pub files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+/// Split the content of a workflow (without volatile metadata) into the
+/// files of the expanded layout, placing node files in `nodes_dir` and
+/// writing them in `format`.
+pub fn expand(content: &Value, nodes_dir: &Path, format: Format) -> anyhow::Result<Expanded> {
+    let mut workflow = canonicalize(content);
+    let mut files = BTreeMap::new();
+    let mut stems = HashSet::new();
+
+    if let Some(nodes) = workflow.get_mut("nodes").and_then(Value::as_array_mut) {
+        for node in nodes.iter_mut() {
+            let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+            let base = file_stem(name);
+            let mut stem = base.clone();
+            let mut n = 1;
+            while !stems.insert(stem.to_lowercase()) {
+                n += 1;
+                stem = format!("{}-{}", base, n);
+            }
+
+            let node_type = node
+                .get("type")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            if let Some(params) = node.get_mut("parameters").and_then(Value::as_object_mut) {
+                for (_, param, ext) in SOURCE_PARAMETERS.iter().filter(|p| p.0 == node_type) {
+                    if let Some(Value::String(source)) = params.get(*param) {
+                        let file = format!("{}.{}", stem, ext);
+                        files.insert(nodes_dir.join(&file), source.clone().into_bytes());
+                        params.insert(param.to_string(), json!({ FILE_REF: file }));
+                    }
+                }
+            }
+
+            let node_path = nodes_dir.join(format!("{}.{}", stem, format.extension()));
+            files.insert(node_path.clone(), format.serialize(node)?);
+            *node = Value::String(node_path.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(Expanded { workflow, files })
+}
+
+/// Whether `path` is relative and made of plain names only, without `..`,
+/// `.` or a root.
+fn is_plain(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Check a node reference from a workflow file. Only files below
+/// `nodes_dir` may be read, so a workflow arriving through a pull request
+/// cannot make `push` upload other files.
+fn node_file(reference: &str, nodes_dir: &Path) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(reference);
+    match path.strip_prefix(nodes_dir) {
+        Ok(rest) if is_plain(&path) && rest.components().next().is_some() => Ok(path),
+        _ => Err(anyhow::anyhow!(
+            "Node file {} is outside {}",
+            reference,
+            nodes_dir.display()
+        )),
+    }
+}
+
+/// Rebuild a workflow from its expanded form, reading the files it refers
+/// to with `read` (paths relative to the workflow file). Node references
+/// must point below `nodes_dir` and extracted parameters must name a file
+/// next to their node. Workflows that are not expanded are returned
+/// unchanged.
+pub fn assemble(
+    workflow: &Value,
+    nodes_dir: &Path,
+    mut read: impl FnMut(&Path) -> anyhow::Result<Vec<u8>>,
+) -> anyhow::Result<Value> {
+    let mut workflow = workflow.clone();
+    let Some(nodes) = workflow.get_mut("nodes").and_then(Value::as_array_mut) else {
+        return Ok(workflow);
+    };
+    for node in nodes.iter_mut() {
+        let Value::String(reference) = node else {
+            continue;
+        };
+        let node_path = node_file(reference, nodes_dir)?;
+        let data = read(&node_path)?;
+        let mut value = Format::from_path(&node_path)
+            .parse(&data)
+            .with_context(|| format!("Failed to parse {}", node_path.display()))?;
+        if let Some(params) = value.get_mut("parameters").and_then(Value::as_object_mut) {
+            for param in params.values_mut() {
+                let Some(file) = extracted_file(param) else {
+                    continue;
+                };
+                let mut components = Path::new(file).components();
+                if !matches!(
+                    (components.next(), components.next()),
+                    (Some(Component::Normal(_)), None)
+                ) {
+                    return Err(anyhow::anyhow!(
+                        "{} refers to {}, which is not a file next to it",
+                        node_path.display(),
+                        file
+                    ));
+                }
+                let source_path = node_path.with_file_name(file);
+                let source = String::from_utf8(read(&source_path)?)
+                    .with_context(|| format!("{} is not valid UTF-8", source_path.display()))?;
+                *param = Value::String(source);
+            }
+        }
+        *node = value;
+    }
+    Ok(workflow)
+}
+
+/// The file name an extracted parameter refers to.
+fn extracted_file(param: &Value) -> Option<&str> {
+    let obj: &Map<String, Value> = param.as_object()?;
+    if obj.len() != 1 {
+        return None;
+    }
+    obj.get(FILE_REF)?.as_str()
+}
+
+/// Read the workflow stored at `path` in either layout.
+///
+/// Node files are only read if they really are inside the node directory:
+/// a symlink leading elsewhere is rejected, so `push` cannot upload files
+/// from outside the repository.
+pub fn load(path: &Path) -> anyhow::Result<Value> {
+    let json = workflow::read(path)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let nodes = nodes_dir(path);
+    let relative = nodes.strip_prefix(dir).unwrap_or(&nodes);
+    let mut root = None;
+    assemble(&json, relative, |file| {
+        let root = match &root {
+            Some(root) => root,
+            None => {
+                let base = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+                root.insert(base.canonicalize()?.join(relative))
+            }
+        };
+        let file = dir.join(file);
+        let real = file
+            .canonicalize()
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        if !real.starts_with(root) {
+            anyhow::bail!(
+                "{} is a link to {}, outside {}",
+                file.display(),
+                real.display(),
+                relative.display()
+            );
+        }
+        fs::read(&real).with_context(|| format!("Failed to read {}", file.display()))
+    })
+}
+
+/// The node files the workflow file at `path` currently refers to, with
+/// the source files of their extracted parameters. These are the only files
+/// in the node directory that saving or removing the workflow may delete.
+fn owned_files(path: &Path) -> BTreeSet<PathBuf> {
+    let mut owned = BTreeSet::new();
+    let Ok(json) = workflow::read(path) else {
+        return owned;
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let nodes = nodes_dir(path);
+    let relative = nodes.strip_prefix(dir).unwrap_or(&nodes);
+    let references = json.get("nodes").and_then(Value::as_array).into_iter().flatten();
+    for reference in references.filter_map(Value::as_str) {
+        let Ok(node_path) = node_file(reference, relative) else {
+            continue;
+        };
+        let node_path = dir.join(node_path);
+        let is_file = fs::symlink_metadata(&node_path).is_ok_and(|m| m.is_file());
+        let node = is_file
+            .then(|| fs::read(&node_path).ok())
+            .flatten()
+            .and_then(|data| Format::from_path(&node_path).parse(&data).ok());
+        let params = node
+            .as_ref()
+            .and_then(|n| n.get("parameters"))
+            .and_then(Value::as_object);
+        for file in params.into_iter().flat_map(|p| p.values()).filter_map(extracted_file) {
+            if Path::new(file).components().count() == 1 && is_plain(Path::new(file)) {
+                owned.insert(node_path.with_file_name(file));
+            }
+        }
+        owned.insert(node_path);
+    }
+    owned
+}
+
+/// Remove the node directory of `path` if nothing is left in it.
+fn remove_empty_nodes_dir(path: &Path) {
+    // Fails, and keeps the directory, when it holds files of the user.
+    let _ = fs::remove_dir(nodes_dir(path));
+}
+
+/// The files a workflow is written to.
+struct Rendered {
+    /// File contents keyed by full path.
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    /// The node directory, if the layout uses one.
+    nodes: Option<PathBuf>,
+}
+
+fn render(path: &Path, json: &Value, layout: Layout) -> anyhow::Result<Rendered> {
+    let (content, _) = split_metadata(json);
+    let format = Format::from_path(path);
+    match layout {
+        Layout::Single => Ok(Rendered {
+            files: BTreeMap::from([(path.to_path_buf(), workflow::to_canonical(json, format)?)]),
+            nodes: None,
+        }),
+        Layout::Expanded => {
+            let dir = path.parent().unwrap_or(Path::new(""));
+            let nodes = nodes_dir(path);
+            let relative = nodes.strip_prefix(dir).unwrap_or(&nodes);
+            let expanded = expand(&content, relative, format)?;
+            let mut files: BTreeMap<_, _> = expanded
+                .files
+                .into_iter()
+                .map(|(file, data)| (dir.join(file), data))
+                .collect();
+            files.insert(path.to_path_buf(), format.serialize(&expanded.workflow)?);
+            Ok(Rendered {
+                files,
+                nodes: Some(nodes),
+            })
+        }
+    }
+}
+
+/// The layout to write `path` in: `layout` if given, else the layout the
+/// file already has, else the project setting.
+fn resolve(path: &Path, layout: Option<Layout>) -> anyhow::Result<Layout> {
+    if let Some(layout) = layout.or_else(|| Layout::detect(path)) {
+        return Ok(layout);
+    }
+    let dir = path.parent().unwrap_or(Path::new(""));
+    Ok(ProjectConfig::find(dir)?.layout.unwrap_or_default())
+}
+
+/// Where to store the workflow pulled into `dir`: `workflow.json` or
+/// `workflow.yaml` depending on `format` if given, else on the file already
+/// there, else on the project setting.
+pub fn workflow_path(dir: &Path, format: Option<Format>) -> anyhow::Result<PathBuf> {
+    let format = match format {
+        Some(format) => format,
+        None => [Format::Json, Format::Yaml]
+            .into_iter()
+            .find(|f| dir.join(f.workflow_file()).is_file())
+            .map_or_else(
+                || ProjectConfig::find(dir).map(|p| p.format.unwrap_or_default()),
+                Ok,
+            )?,
+    };
+    Ok(dir.join(format.workflow_file()))
+}
+
+/// The workflow file next to `path` in another format, if there is one.
+/// It holds the same workflow and is replaced when `path` is written.
+pub fn superseded(path: &Path) -> Option<PathBuf> {
+    if !is_workflow_file(path) {
+        return None;
+    }
+    [Format::Json, Format::Yaml]
+        .into_iter()
+        .map(|f| path.with_file_name(f.workflow_file()))
+        .find(|other| other != path && other.is_file())
+}
+
+/// Delete the workflow stored at `path` with its sidecar and the node files
+/// it refers to. Other files in the node directory are kept.
+pub fn remove(path: &Path) -> anyhow::Result<()> {
+    let owned = owned_files(path);
+    for file in [path.to_path_buf(), metadata_path(path)]
+        .into_iter()
+        .chain(owned)
+    {
+        if fs::symlink_metadata(&file).is_ok() {
+            fs::remove_file(&file)
+                .with_context(|| format!("Failed to remove {}", file.display()))?;
+        }
+    }
+    remove_empty_nodes_dir(path);
+    Ok(())
+}
+
+/// Node files the workflow file at `path` refers to that are not part of
+/// `files`.
+fn stale_files(path: &Path, files: &BTreeMap<PathBuf, Vec<u8>>) -> Vec<PathBuf> {
+    owned_files(path)
+        .into_iter()
+        .filter(|p| !files.contains_key(p) && fs::symlink_metadata(p).is_ok())
+        .collect()
+}
+
+/// Whether writing `json` to `path` would change the workflow on disk,
+/// ignoring volatile metadata.
+pub fn differs(path: &Path, json: &Value, layout: Option<Layout>) -> anyhow::Result<bool> {
+    let layout = resolve(path, layout)?;
+    let Rendered { files, .. } = render(path, json, layout)?;
+    let changed = files
+        .iter()
+        .any(|(file, data)| fs::read(file).map_or(true, |existing| existing != *data));
+    Ok(changed || !stale_files(path, &files).is_empty())
+}
+
+/// Write `json` to `path` in canonical form using `layout`, or the layout
+/// of the existing file if `None`, with volatile metadata in the sidecar.
+/// Node files the previous version referred to that no longer belong to the
+/// workflow are removed, and files whose content is unchanged are not
+/// touched.
+///
+/// Returns whether the workflow itself changed on disk.
+pub fn save(path: &Path, json: &Value, layout: Option<Layout>) -> anyhow::Result<bool> {
+    let layout = resolve(path, layout)?;
+    let Rendered { files, nodes } = render(path, json, layout)?;
+    let mut changed = false;
+    for stale in stale_files(path, &files) {
+        fs::remove_file(&stale)
+            .with_context(|| format!("Failed to remove {}", stale.display()))?;
+        changed = true;
+    }
+    match &nodes {
+        Some(nodes) => fs::create_dir_all(nodes)
+            .with_context(|| format!("Failed to create directory {}", nodes.display()))?,
+        None => remove_empty_nodes_dir(path),
+    }
+    for (file, data) in &files {
+        changed |= write_if_changed(file, data)?;
+    }
+    let (_, metadata) = split_metadata(json);
+    write_if_changed(
+        &metadata_path(path),
+        &Format::from_path(path).serialize(&metadata)?,
+    )?;
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::{WORKFLOW_FILE, WORKFLOW_YAML_FILE};
+
+    fn workflow() -> Value {
+        json!({
+            "id": "1",
+            "name": "Flow",
+            "updatedAt": "2024-01-01T00:00:00.000Z",
+            "nodes": [
+                {
+                    "id": "b",
+                    "name": "Transform: items",
+                    "type": "n8n-nodes-base.code",
+                    "parameters": {"jsCode": "return items.map(i => i);\n", "mode": "runOnceForAllItems"}
+                },
+                {
+                    "id": "c",
+                    "name": "Load",
+                    "type": "n8n-nodes-base.postgres",
+                    "parameters": {"operation": "executeQuery", "query": "SELECT 1;"}
+                },
+                {
+                    "id": "a",
+                    "name": "Webhook",
+                    "type": "n8n-nodes-base.webhook",
+                    "parameters": {"path": "hook"},
+                    "position": [0, 0]
+                }
+            ],
+            "connections": {"Webhook": {"main": [[{"node": "Transform: items", "type": "main", "index": 0}]]}},
+            "settings": {}
+        })
+    }
+
+    #[test]
+    fn expanded_workflows_round_trip() {
+        let wf = workflow();
+        let (content, _) = split_metadata(&wf);
+        let expanded = expand(&content, Path::new(NODES_DIR), Format::Json).unwrap();
+
+        assert_eq!(
+            expanded.workflow["nodes"],
+            json!([
+                "nodes/Load.json",
+                "nodes/Transform_ items.json",
+                "nodes/Webhook.json"
+            ])
+        );
+        assert_eq!(
+            expanded.files[Path::new("nodes/Transform_ items.js")],
+            b"return items.map(i => i);\n"
+        );
+        assert_eq!(expanded.files[Path::new("nodes/Load.sql")], b"SELECT 1;");
+        let node: Value =
+            serde_json::from_slice(&expanded.files[Path::new("nodes/Load.json")]).unwrap();
+        assert_eq!(node["parameters"]["query"], json!({"$file": "Load.sql"}));
+
+        let assembled = assemble(&expanded.workflow, Path::new(NODES_DIR), |p| {
+            expanded
+                .files
+                .get(p)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("missing {}", p.display()))
+        })
+        .unwrap();
+        assert_eq!(assembled, canonicalize(&content));
+        assert_eq!(
+            workflow::sanitize_for_update(&assembled),
+            workflow::sanitize_for_update(&canonicalize(&wf))
+        );
+    }
+
+    #[test]
+    fn references_outside_the_node_directory_are_rejected() {
+        let read = |p: &Path| -> anyhow::Result<Vec<u8>> {
+            Ok(match p.extension().and_then(|e| e.to_str()) {
+                Some("json") => br#"{"parameters": {"jsCode": {"$file": "../../.env"}}}"#.to_vec(),
+                _ => b"SECRET=1".to_vec(),
+            })
+        };
+        for reference in ["../../.env", "/etc/passwd", "nodes/../../.env", "other/A.json", "nodes"] {
+            let wf = json!({"nodes": [reference]});
+            assert!(assemble(&wf, Path::new(NODES_DIR), read).is_err(), "{}", reference);
+        }
+        let wf = json!({"nodes": ["nodes/A.json"]});
+        let err = assemble(&wf, Path::new(NODES_DIR), read).unwrap_err();
+        assert!(err.to_string().contains("../../.env"));
+    }
+
+    #[test]
+    fn saves_and_loads_the_expanded_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WORKFLOW_FILE);
+        let wf = workflow();
+
+        assert!(save(&path, &wf, Some(Layout::Expanded)).unwrap());
+        assert_eq!(Layout::detect(&path), Some(Layout::Expanded));
+        assert!(dir.path().join("nodes/Webhook.json").exists());
+        assert!(!differs(&path, &wf, None).unwrap());
+        assert_eq!(load(&path).unwrap(), canonicalize(&split_metadata(&wf).0));
+
+        // Removing a node removes its files and keeps the layout.
+        let mut smaller = wf.clone();
+        smaller["nodes"].as_array_mut().unwrap().remove(1);
+        assert!(save(&path, &smaller, None).unwrap());
+        assert!(!dir.path().join("nodes/Load.json").exists());
+        assert!(!dir.path().join("nodes/Load.sql").exists());
+
+        // Switching back to a single file drops the node directory.
+        assert!(save(&path, &smaller, Some(Layout::Single)).unwrap());
+        assert!(!dir.path().join(NODES_DIR).exists());
+        assert_eq!(Layout::detect(&path), Some(Layout::Single));
+        assert_eq!(
+            load(&path).unwrap(),
+            canonicalize(&split_metadata(&smaller).0)
+        );
+    }
+
+    #[test]
+    fn unrelated_files_in_the_node_directory_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WORKFLOW_FILE);
+        let readme = dir.path().join("nodes/README.md");
+        fs::create_dir_all(readme.parent().unwrap()).unwrap();
+        fs::write(&readme, "notes").unwrap();
+
+        // A single-file pull neither removes the directory nor treats it as
+        // the expanded layout.
+        assert!(save(&path, &workflow(), None).unwrap());
+        assert_eq!(Layout::detect(&path), Some(Layout::Single));
+        assert!(!differs(&path, &workflow(), None).unwrap());
+        assert!(readme.exists());
+
+        // Expanding and collapsing again only touch the node files.
+        save(&path, &workflow(), Some(Layout::Expanded)).unwrap();
+        let mut smaller = workflow();
+        smaller["nodes"].as_array_mut().unwrap().remove(1);
+        save(&path, &smaller, None).unwrap();
+        assert!(!dir.path().join("nodes/Load.json").exists());
+        save(&path, &smaller, Some(Layout::Single)).unwrap();
+        assert!(!dir.path().join("nodes/Webhook.json").exists());
+        remove(&path).unwrap();
+        assert_eq!(fs::read_to_string(&readme).unwrap(), "notes");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn node_files_linking_outside_are_not_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = dir.path().join("secret.txt");
+        fs::write(&secret, "SECRET=1").unwrap();
+        let path = dir.path().join("flow").join(WORKFLOW_FILE);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        save(&path, &workflow(), Some(Layout::Expanded)).unwrap();
+        assert!(load(&path).is_ok());
+
+        let code = path.with_file_name("nodes/Transform_ items.js");
+        fs::remove_file(&code).unwrap();
+        std::os::unix::fs::symlink(&secret, &code).unwrap();
+        let err = load(&path).unwrap_err();
+        assert!(err.to_string().contains("outside"), "{:#}", err);
+    }
+
+    #[test]
+    fn metadata_goes_to_a_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WORKFLOW_FILE);
+        let mut wf = json!({"id": "1", "name": "Flow", "nodes": [], "versionId": "v1"});
+
+        assert!(save(&path, &wf, None).unwrap());
+        wf["versionId"] = json!("v2");
+        assert!(!differs(&path, &wf, None).unwrap());
+        assert!(!save(&path, &wf, None).unwrap());
+
+        let metadata: Value =
+            serde_json::from_slice(&fs::read(metadata_path(&path)).unwrap()).unwrap();
+        assert_eq!(metadata, json!({"versionId": "v2"}));
+        assert_eq!(Layout::detect(&path), Some(Layout::Single));
+    }
+
+    #[test]
+    fn yaml_workflows_replace_json_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join(WORKFLOW_FILE);
+        save(&json_path, &workflow(), Some(Layout::Expanded)).unwrap();
+        assert_eq!(workflow_path(dir.path(), None).unwrap(), json_path);
+
+        let yaml_path = workflow_path(dir.path(), Some(Format::Yaml)).unwrap();
+        assert_eq!(yaml_path, dir.path().join(WORKFLOW_YAML_FILE));
+        assert_eq!(superseded(&yaml_path), Some(json_path.clone()));
+        remove(&json_path).unwrap();
+        save(&yaml_path, &workflow(), Some(Layout::Expanded)).unwrap();
+
+        assert!(!json_path.exists() && !dir.path().join("workflow.meta.json").exists());
+        assert!(dir.path().join("workflow.meta.yaml").exists());
+        let code = dir.path().join("nodes/Transform_ items.yaml");
+        assert!(
+            fs::read_to_string(code)
+                .unwrap()
+                .contains("$file: Transform_ items.js")
+        );
+        assert_eq!(superseded(&yaml_path), None);
+        assert_eq!(workflow_path(dir.path(), None).unwrap(), yaml_path);
+        assert_eq!(
+            load(&yaml_path).unwrap(),
+            canonicalize(&split_metadata(&workflow()).0)
+        );
+    }
+}
@@ -1,11 +1,24 @@
 pub mod api;
 pub mod config;
+pub mod diff;
+pub mod error;
+pub mod git;
+pub mod layout;
+pub mod lock;
+pub mod merge;
+pub mod mirror;
 pub mod nodes;
+pub mod outdated;
+pub mod retry;
+pub mod secrets;
+pub mod status;
+pub mod sync;
+pub mod workflow;
 
 #[cfg(test)]
 mod tests {
     use url::Url;
-    use wiremock::matchers::{header, method, path};
+    use wiremock::matchers::{header, method, path, query_param};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
@@ -25,7 +38,8 @@ mod tests {
             api_key: "test-key".into(),
             host: Url::parse(&server.uri()).unwrap(),
         };
-        let workflows = crate::api::list_workflows(&config).await.unwrap();
+        let client = crate::api::N8nClient::new(config).unwrap();
+        let workflows = client.list_workflows(&Default::default(), 100).await.unwrap();
         // ensure the mock was hit
         assert_eq!(server.received_requests().await.unwrap().len(), 1);
         assert_eq!(workflows.len(), 1);
@@ -33,6 +47,126 @@ mod tests {
         assert_eq!(workflows[0].name, "Test");
     }
 
+    #[tokio::test]
+    async fn list_workflows_follows_next_cursor() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .and(query_param("limit", "2"))
+            .and(query_param("cursor", "page-2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{ "id": "3", "name": "Third" }],
+                "nextCursor": null
+            })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .and(query_param("limit", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{ "id": "1", "name": "First" }, { "id": "2", "name": "Second" }],
+                "nextCursor": "page-2"
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+
+        let config = crate::config::N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        };
+        let client = crate::api::N8nClient::new(config).unwrap();
+        let workflows = client.list_workflows(&Default::default(), 2).await.unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+        let ids: Vec<_> = workflows.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn list_workflows_stops_on_a_repeated_cursor() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{ "id": "1", "name": "First" }],
+                "nextCursor": "stuck"
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let config = crate::config::N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        };
+        let client = crate::api::N8nClient::new(config).unwrap();
+        let err = client.list_workflows(&Default::default(), 1).await.unwrap_err();
+        assert!(matches!(
+            err,
+            crate::api::ApiError::RepeatedCursor { ref cursor } if cursor == "stuck"
+        ));
+    }
+
+    #[tokio::test]
+    async fn list_workflows_sends_filters() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .and(query_param("active", "true"))
+            .and(query_param("tags", "prod,billing"))
+            .and(query_param("name", "Invoices"))
+            .and(query_param("projectId", "p1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{ "id": "7", "name": "Invoices" }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = crate::config::N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        };
+        let client = crate::api::N8nClient::new(config).unwrap();
+        let filter = crate::api::ListFilter {
+            active: Some(true),
+            tags: vec!["prod".into(), "billing".into()],
+            name: Some("Invoices".into()),
+            project_id: Some("p1".into()),
+        };
+        let workflows = client.list_workflows(&filter, 100).await.unwrap();
+        assert_eq!(workflows.len(), 1);
+        assert_eq!(workflows[0].id, "7");
+    }
+
+    #[tokio::test]
+    async fn requests_share_client_headers() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/1"))
+            .and(header("X-N8N-API-KEY", "test-key"))
+            .and(header("User-Agent", crate::api::USER_AGENT))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": "1"})),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let config = crate::config::N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        };
+        let client = crate::api::N8nClient::new(config).unwrap();
+        client.get_workflow("1").await.unwrap();
+        client.get_workflow("1").await.unwrap();
+    }
+
     #[tokio::test]
     async fn create_workflow() {
         let server = MockServer::start().await;
@@ -51,7 +185,8 @@ mod tests {
             api_key: "test-key".into(),
             host: Url::parse(&server.uri()).unwrap(),
         };
-        let wf = crate::api::create_workflow(&cfg, "New").await.unwrap();
+        let client = crate::api::N8nClient::new(cfg).unwrap();
+        let wf = client.create_workflow("New").await.unwrap();
         assert_eq!(wf.id, "2");
         assert_eq!(wf.name, "New");
     }
@@ -86,12 +221,13 @@ mod tests {
             api_key: "test-key".into(),
             host: Url::parse(&server.uri()).unwrap(),
         };
+        let client = crate::api::N8nClient::new(cfg).unwrap();
 
-        let wf_json = crate::api::get_workflow(&cfg, "42").await.unwrap();
+        let wf_json = client.get_workflow("42").await.unwrap();
         assert_eq!(wf_json["id"], "42");
 
         let update = serde_json::json!({"id": "42", "name": "New"});
-        let updated = crate::api::update_workflow(&cfg, "42", &update)
+        let updated = client.update_workflow("42", &update)
             .await
             .unwrap();
         assert_eq!(updated.name, "New");
@@ -99,4 +235,114 @@ mod tests {
         // ensure both mocks were hit
         assert_eq!(server.received_requests().await.unwrap().len(), 2);
     }
+
+    #[tokio::test]
+    async fn update_workflow_reports_validation_errors() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/workflows/42"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "message": "request/body/nodes must be array"
+            })))
+            .mount(&server)
+            .await;
+
+        let cfg = crate::config::N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        };
+        let client = crate::api::N8nClient::new(cfg).unwrap();
+        let err = client
+            .update_workflow("42", &serde_json::json!({"nodes": {}}))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::api::ApiError::Validation { ref message } if message == "request/body/nodes must be array"
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_workflow_retries_transient_failures() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/9"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/9"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/9"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": "9"})),
+            )
+            .mount(&server)
+            .await;
+
+        let cfg = crate::config::N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        };
+        let client = crate::api::N8nClient::new(cfg)
+            .unwrap()
+            .with_retry_policy(crate::retry::RetryPolicy {
+                max_retries: 2,
+                base_delay: std::time::Duration::from_millis(1),
+                max_delay: std::time::Duration::from_millis(5),
+            });
+        let wf = client.get_workflow("9").await.unwrap();
+        assert_eq!(wf["id"], "9");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn oversized_retry_after_is_not_waited_for() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/9"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "86400"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cfg = crate::config::N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        };
+        let client = crate::api::N8nClient::new(cfg).unwrap();
+        let err = client.get_workflow("9").await.unwrap_err();
+        assert!(matches!(
+            err,
+            crate::api::ApiError::RateLimited { retry_after: Some(d) } if d.as_secs() == 86400
+        ));
+    }
+
+    #[tokio::test]
+    async fn create_workflow_is_not_retried() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/v1/workflows"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cfg = crate::config::N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        };
+        let client = crate::api::N8nClient::new(cfg).unwrap();
+        let err = client.create_workflow("New").await.unwrap_err();
+        assert!(matches!(err, crate::api::ApiError::Server { .. }));
+    }
 }
@@ -0,0 +1,157 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::workflow::sanitize_for_update;
+
+/// File kept beside pulled workflows that records what was last synced.
+pub const LOCK_FILE: &str = ".n8n-sync.lock";
+
+/// The remote revision a local workflow file was last synced with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockEntry {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+    /// The updatable part of the workflow at that revision, used as the
+    /// common ancestor when merging.
+    pub base: Value,
+}
+
+impl LockEntry {
+    /// Build an entry from a workflow as returned by the server.
+    pub fn from_remote(id: &str, remote: &Value) -> Self {
+        let field = |key: &str| remote.get(key).and_then(Value::as_str).map(str::to_string);
+        Self {
+            id: id.to_string(),
+            updated_at: field("updatedAt"),
+            version_id: field("versionId"),
+            base: sanitize_for_update(remote),
+        }
+    }
+}
+
+/// Lock entries for the workflow files of one directory, keyed by file name.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncLock {
+    #[serde(default)]
+    pub workflows: BTreeMap<String, LockEntry>,
+}
+
+impl SyncLock {
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(LOCK_FILE)
+    }
+
+    /// Read the lock file in `dir`, returning an empty lock if there is none.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = Self::path(dir);
+        match fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, dir: &Path) -> anyhow::Result<PathBuf> {
+        let path = Self::path(dir);
+        let mut data = serde_json::to_vec_pretty(self)?;
+        data.push(b'\n');
+        fs::write(&path, data).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+fn split(workflow_path: &Path) -> (&Path, String) {
+    let dir = workflow_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file = workflow_path
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (dir, file)
+}
+
+/// The lock entry recorded for a workflow file, if any.
+pub fn entry_for(workflow_path: &Path) -> anyhow::Result<Option<LockEntry>> {
+    let (dir, file) = split(workflow_path);
+    Ok(SyncLock::load(dir)?.workflows.remove(&file))
+}
+
+/// Record that `workflow_path` now matches the given remote workflow.
+///
+/// Returns the path of the lock file that was written.
+pub fn record(workflow_path: &Path, entry: LockEntry) -> anyhow::Result<PathBuf> {
+    let (dir, file) = split(workflow_path);
+    let mut lock = SyncLock::load(dir)?;
+    lock.workflows.insert(file, entry);
+    lock.save(dir)
+}
+
+/// Drop the entry of a workflow file that was removed, keeping the lock file
+/// itself even when it becomes empty.
+pub fn forget(workflow_path: &Path) -> anyhow::Result<PathBuf> {
+    let (dir, file) = split(workflow_path);
+    let mut lock = SyncLock::load(dir)?;
+    lock.workflows.remove(&file);
+    lock.save(dir)
+}
+
+/// The workflow ID to use for `workflow_path`: the one given explicitly,
+/// else the one recorded in the lock, else the `id` field of the file.
+pub fn workflow_id(
+    explicit: Option<&str>,
+    workflow_path: &Path,
+    json: &Value,
+) -> anyhow::Result<String> {
+    if let Some(id) = explicit {
+        return Ok(id.to_string());
+    }
+    if let Some(entry) = entry_for(workflow_path)? {
+        return Ok(entry.id);
+    }
+    json.get("id")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("Workflow ID not provided and not found in JSON"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn records_and_reads_entries_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("workflow.json");
+        let remote = json!({
+            "id": "7",
+            "name": "Flow",
+            "nodes": [],
+            "updatedAt": "2024-01-01T00:00:00.000Z",
+            "versionId": "abc"
+        });
+
+        assert_eq!(entry_for(&path).unwrap(), None);
+        record(&path, LockEntry::from_remote("7", &remote)).unwrap();
+
+        let entry = entry_for(&path).unwrap().unwrap();
+        assert_eq!(
+            entry.updated_at.as_deref(),
+            Some("2024-01-01T00:00:00.000Z")
+        );
+        assert_eq!(entry.version_id.as_deref(), Some("abc"));
+        assert_eq!(entry.base, json!({"name": "Flow", "nodes": []}));
+        assert_eq!(entry_for(&dir.path().join("other.json")).unwrap(), None);
+    }
+}
@@ -1,37 +1,60 @@
-use clap::{Parser, Subcommand};
-use dialoguer::Confirm;
-use git2::{Repository, Signature};
-use n8n_workflow_sync::{api, config, nodes};
+use clap::{Args, Parser, Subcommand};
+use dialoguer::{Confirm, Input, Password};
+use n8n_workflow_sync::api::{ApiError, RetryPolicy};
+use n8n_workflow_sync::secrets::{DefaultStore, SecretStore};
+use n8n_workflow_sync::layout::{self, Layout};
+use n8n_workflow_sync::lock::{self, LOCK_FILE, LockEntry};
+use n8n_workflow_sync::mirror::{self, FileStatus, MirrorOptions};
+use n8n_workflow_sync::workflow::{
+    self, Format, WORKFLOW_FILE, WORKFLOW_YAML_FILE, sanitize_for_update, slugify,
+};
+use n8n_workflow_sync::{api, config, diff, git, nodes, outdated, status, sync};
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 use anyhow::Context;
 
-/// Convert a workflow name into a filesystem-friendly slug
-fn slugify(name: &str) -> String {
-    name.to_lowercase()
-        .chars()
-        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
-        .collect::<String>()
-        .trim_matches('-')
-        .to_string()
+mod output;
+
+use output::{
+    ConfigEntry, ConfigReport, DiffReport, ListReport, NewReport, Output, OutputFormat, ProfileSummary,
+    NodesRefreshReport, OutdatedReport, ProfilesReport, PullAllReport, PullReport, PushReport, StatusReport,
+    SyncReport, describe_change,
+};
+
+/// Write `node-versions.json` into `dir`. Node versions are informational,
+/// so failing to get them only produces a warning.
+async fn write_node_versions(client: &api::N8nClient, source: &nodes::NodeSource, dir: &Path) {
+    if let Err(e) = nodes::save_node_versions(client, source, dir).await {
+        tracing::warn!("not writing {}: {:#}", nodes::NODE_VERSIONS_FILE, e);
+    }
 }
 
 /// Attempt to locate a default workflow JSON file in the current directory.
 ///
-/// Preference is given to a file named `workflow.json`. If exactly one other
-/// `.json` file exists, that is returned. Otherwise an error is produced.
+/// Preference is given to a file named `workflow.json`, then
+/// `workflow.yaml`. If exactly one other `.json` or `.yaml` file exists, that
+/// is returned. Otherwise an error is produced.
 fn default_json_path() -> anyhow::Result<PathBuf> {
-    let preferred = PathBuf::from("workflow.json");
-    if preferred.exists() {
-        return Ok(preferred);
+    for preferred in [WORKFLOW_FILE, WORKFLOW_YAML_FILE] {
+        let preferred = PathBuf::from(preferred);
+        if preferred.exists() {
+            return Ok(preferred);
+        }
     }
 
     let mut json_files = vec![];
     for entry in fs::read_dir(".")? {
         let entry = entry?;
         let path = entry.path();
-        if path.extension().map(|ext| ext == "json").unwrap_or(false) {
+        if path
+            .extension()
+            .map(|ext| ext == "json" || ext == "yaml" || ext == "yml")
+            .unwrap_or(false)
+            && !workflow::is_metadata_file(&path)
+        {
             json_files.push(path);
         }
     }
@@ -39,33 +62,18 @@ fn default_json_path() -> anyhow::Result<PathBuf> {
     if json_files.len() == 1 {
         Ok(json_files.remove(0))
     } else if json_files.is_empty() {
-        Err(anyhow::anyhow!("No JSON files found"))
+        Err(anyhow::anyhow!("No JSON or YAML files found"))
     } else {
         Err(anyhow::anyhow!(
-            "Multiple JSON files found. Please specify which one to push"
+            "Multiple JSON or YAML files found. Please specify which one to push"
         ))
     }
 }
 
-/// Remove fields not accepted by the Public API when updating a workflow.
-fn sanitize_for_update(json: &serde_json::Value) -> serde_json::Value {
-    use serde_json::{Map, Value};
-
-    let allowed = [
-        "name",
-        "nodes",
-        "connections",
-        "settings",
-        "staticData",
-    ];
-
-    let mut obj = Map::new();
-    for key in allowed.iter() {
-        if let Some(v) = json.get(*key) {
-            obj.insert((*key).to_string(), v.clone());
-        }
-    }
-    Value::Object(obj)
+/// Read and parse a local workflow JSON file, reassembling it if it uses
+/// the expanded layout.
+fn read_workflow(path: &Path) -> anyhow::Result<serde_json::Value> {
+    layout::load(path)
 }
 
 #[derive(Parser)]
@@ -74,16 +82,31 @@ fn sanitize_for_update(json: &serde_json::Value) -> serde_json::Value {
     version,
     about,
     long_about = "Pull, edit and push n8n workflows using Git. \n\
-Set the N8N_HOST and N8N_API_KEY environment variables to authenticate with your n8n instance.\n\n\
+Run `n8n-workflow-sync config init` once to store your n8n URL and API key, or set the\n\
+N8N_HOST and N8N_API_KEY environment variables, which override the stored values.\n\n\
 Examples:\n  \
 n8n-workflow-sync list\n  \
 n8n-workflow-sync new \"My New Workflow\"\n  \
 n8n-workflow-sync pull 123 workflow.json\n  \
+n8n-workflow-sync pull --all workflows --tag prod\n  \
 n8n-workflow-sync push 123 workflow.json",
-    after_help = "ENVIRONMENT VARIABLES:\n    N8N_HOST     Base URL of the n8n instance (e.g., https://your-n8n.example.com)\n    N8N_API_KEY  API key for authentication",
+    after_help = "ENVIRONMENT VARIABLES:\n    N8N_HOST             Base URL of the n8n instance (e.g., https://your-n8n.example.com)\n    N8N_API_KEY          API key for authentication\n    N8N_API_KEY_FILE     File containing the API key (e.g. a Docker secret)\n    N8N_SYNC_CONFIG      Path of the config file to use instead of the default\n    N8N_PROFILE          Named profile to use (same as --profile)\n    N8N_SYNC_PASSPHRASE  Passphrase for the encrypted key file used when no OS keyring exists\n\n\
+EXIT CODES:\n    1   General error\n    3   Authentication failed (401)\n    4   Forbidden (403)\n    5   Workflow or endpoint not found (404)\n    6   Conflict (409, or the workflow changed on the server since pull)\n    7   Validation rejected (400/422)\n    8   Rate limited (429)\n    9   Server error (5xx)\n    10  Network or timeout error\n    11  Unexpected response body",
     arg_required_else_help = true
 )]
 struct Cli {
+    /// Output format for command results
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// How many times to retry requests that fail with transient errors
+    #[arg(long, global = true, default_value_t = RetryPolicy::default().max_retries)]
+    retries: u32,
+
+    /// Named profile from the config file to use
+    #[arg(long, global = true, env = "N8N_PROFILE")]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -91,7 +114,10 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// List all workflows from the n8n server
-    List,
+    List {
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
     /// Create a new workflow with the given name and download it as JSON
     ///
     /// This command creates a new workflow on the n8n server, downloads the workflow
@@ -103,18 +129,53 @@ enum Commands {
         name: String,
     },
     /// Download a workflow JSON file from the server
+    ///
+    /// With `--all [DIR]`, every workflow matching the filters is saved to
+    /// DIR/<slug>/workflow.json and recorded in a single commit of DIR.
+    ///
+    /// With `--layout expanded`, each node is written to its own file under
+    /// `nodes/`, with code, SQL and HTML parameters in native files.
     Pull {
         /// ID of the workflow to download
-        id: String,
+        #[arg(required_unless_present = "all")]
+        id: Option<String>,
         /// Optional path to save the workflow JSON. Can be a directory
         /// or a file. Defaults to a directory named after the workflow.
         path: Option<PathBuf>,
+        /// Download every workflow into DIR (defaults to the current directory)
+        #[arg(
+            long,
+            value_name = "DIR",
+            num_args = 0..=1,
+            default_missing_value = ".",
+            conflicts_with_all = ["id", "path"]
+        )]
+        all: Option<PathBuf>,
+        /// Number of workflows downloaded at the same time (with --all)
+        #[arg(long, default_value_t = mirror::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+        /// File format to save workflows in. Defaults to the format of an
+        /// existing file, then the `format` project setting, then JSON.
+        /// Ignored when PATH names a file, whose extension decides.
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        /// How to lay out workflows on disk. Defaults to the layout of an
+        /// existing file, then the `layout` project setting, then a single
+        /// file.
+        #[arg(long, value_enum)]
+        layout: Option<Layout>,
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// Upload a modified workflow JSON file to the server
     ///
     /// If no ID or path is provided, the command will attempt to
     /// locate a single JSON file in the current directory and read
     /// the `id` field from it.
+    ///
+    /// The push is refused if the workflow was edited on the server since it
+    /// was pulled, listing the changes that would be lost. Use `sync` to
+    /// merge both sides or `--force` to overwrite the server copy.
     Push {
         /// ID of the workflow to update. If omitted, the ID will be
         /// read from the JSON file.
@@ -123,52 +184,479 @@ enum Commands {
         /// `workflow.json` or the only JSON file in the current
         /// directory.
         path: Option<PathBuf>,
+        /// Overwrite the server copy even if it changed since the last pull
+        #[arg(long)]
+        force: bool,
+    },
+    /// Reconcile a local workflow JSON file with the server
+    ///
+    /// Local and remote edits are merged against the revision recorded by the
+    /// last pull, push or sync. Changes that do not overlap are applied in
+    /// both directions; otherwise the conflicting fields are listed and
+    /// nothing is changed.
+    Sync {
+        /// ID of the workflow to sync. Defaults to the ID recorded at pull
+        /// time or found in the JSON file.
+        id: Option<String>,
+        /// Path to the workflow JSON file. Defaults to `workflow.json` or
+        /// the only JSON file in the current directory.
+        path: Option<PathBuf>,
+    },
+    /// Show how a local workflow JSON file differs from the server copy
+    ///
+    /// Both sides are reduced to the fields `push` uploads, then compared
+    /// node by node: added, removed and renamed nodes, parameter changes,
+    /// connection changes and settings changes.
+    Diff {
+        /// ID of the workflow to compare against. Defaults to the ID
+        /// recorded at pull time or found in the JSON file.
+        id: Option<String>,
+        /// Path to the workflow JSON file. Defaults to `workflow.json` or
+        /// the only JSON file in the current directory.
+        path: Option<PathBuf>,
+        /// Print an RFC 6902 JSON Patch that turns the remote workflow into
+        /// the local one
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show which pulled workflows differ from the server
+    ///
+    /// Scans DIR for pulled workflows and compares each with its last synced
+    /// revision and the server copy. Workflows on the server that were never
+    /// pulled are listed as untracked.
+    Status {
+        /// Directory to scan (defaults to the current directory)
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Show nodes that use an old version, are deprecated or are unknown
+    ///
+    /// Compares the typeVersion of each node with the node-versions.json
+    /// written when the workflow was pulled, or with the configured node
+    /// source when there is none.
+    Outdated {
+        /// Workflow file or directory to scan (defaults to the current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Exit with an error when any node is outdated or deprecated, e.g. in CI
+        #[arg(long)]
+        check: bool,
+    },
+    /// Manage the catalogue of n8n node versions
+    Nodes {
+        #[command(subcommand)]
+        action: NodesCommand,
+    },
+    /// View or change the stored configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
     },
     /// Download and replace the binary with the latest release from GitHub
     Upgrade,
 }
 
+#[derive(Subcommand)]
+enum NodesCommand {
+    /// Read the latest node versions from the configured source
+    ///
+    /// Versions read from GitHub are cached; pulls reuse them for a day and
+    /// fall back to them when GitHub cannot be reached. Set the source with
+    /// `config set node_source` (instance, file:PATH, tag, tag:NAME, path:DIR
+    /// or github).
+    Refresh,
+}
+
+/// Filters shared by commands that operate on many workflows.
+#[derive(Args)]
+struct FilterArgs {
+    /// Only include active workflows
+    #[arg(long, conflicts_with = "inactive")]
+    active: bool,
+    /// Only include inactive workflows
+    #[arg(long)]
+    inactive: bool,
+    /// Only include workflows carrying this tag (repeatable)
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+    /// Only include workflows with this name
+    #[arg(long)]
+    name: Option<String>,
+    /// Only include workflows belonging to this project ID
+    #[arg(long = "project", value_name = "PROJECT_ID")]
+    project: Option<String>,
+    /// Number of workflows requested per page while paginating (1-250)
+    #[arg(long, default_value_t = api::DEFAULT_PAGE_LIMIT, value_parser = clap::value_parser!(u32).range(1..=250))]
+    limit: u32,
+}
+
+impl FilterArgs {
+    fn to_filter(&self) -> api::ListFilter {
+        api::ListFilter {
+            active: match (self.active, self.inactive) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            tags: self.tags.clone(),
+            name: self.name.clone(),
+            project_id: self.project.clone(),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Interactively set up the n8n URL and API key
+    Init,
+    /// Store a config value (keys: host, api_key_command, commit_message, node_source, default_profile)
+    Set {
+        /// Name of the setting
+        key: String,
+        /// New value
+        value: String,
+    },
+    /// Print a stored config value
+    Get {
+        /// Name of the setting
+        key: String,
+    },
+    /// Store the API key in the system keyring (or encrypted file fallback)
+    ///
+    /// The key is prompted for without echo. When stdin is not a terminal it
+    /// is read from the first line of stdin instead.
+    SetKey,
+    /// Show the effective configuration and where each value comes from
+    Show,
+    /// List the configured profiles
+    Profiles,
+}
+
+/// Save the API key for `profile` to the secret store, dropping any
+/// plain-text copy left in the config file by older versions.
+fn store_api_key(
+    file: &mut config::ConfigFile,
+    profile: &str,
+    api_key: &str,
+    out: &Output,
+) -> anyhow::Result<()> {
+    DefaultStore::from_env()?
+        .set(profile, api_key)
+        .with_context(|| "Failed to store the API key")?;
+    if file.profile_mut(profile).api_key.take().is_some() {
+        file.store()?;
+    }
+    out.info(format!("✓ API key for profile \"{}\" saved to secure storage", profile));
+    Ok(())
+}
+
+/// Prompt for the n8n URL and API key and save them to the given profile.
+fn setup_wizard(out: &Output, profile: &str) -> anyhow::Result<()> {
+    let mut file = config::ConfigFile::load()?;
+    let current = file.profile(profile).cloned().unwrap_or_default();
+
+    let host: String = Input::new()
+        .with_prompt("n8n API endpoint")
+        .with_initial_text(current.host.unwrap_or_default())
+        .validate_with(|input: &String| {
+            config::normalize_host(input)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .interact_text()?;
+    file.set(profile, "host", &host)?;
+
+    let path = file.store()?;
+    out.info(format!(
+        "✓ Config for profile \"{}\" written to {}",
+        profile,
+        path.display()
+    ));
+
+    let has_key = DefaultStore::from_env()?.get(profile).ok().flatten().is_some();
+    let api_key = Password::new()
+        .with_prompt(if has_key {
+            "API Key (leave empty to keep the current one)"
+        } else {
+            "API Key"
+        })
+        .allow_empty_password(has_key)
+        .interact()?;
+    if !api_key.is_empty() {
+        store_api_key(&mut file, profile, &api_key, out)?;
+    }
+    Ok(())
+}
+
+fn run_config(action: ConfigCommand, profile: Option<&str>, out: &Output) -> anyhow::Result<()> {
+    let mut file = config::ConfigFile::load()?;
+    let use_env = profile.is_none();
+    let profile = file.active_profile(profile);
+
+    match action {
+        ConfigCommand::Init => setup_wizard(out, &profile)?,
+        ConfigCommand::Set { key, value } => {
+            file.set(&profile, &key, &value)?;
+            let path = file.store()?;
+            if key == "default_profile" {
+                out.info(format!("✓ Default profile set to \"{}\"", value));
+            } else {
+                out.info(format!(
+                    "✓ Set {} for profile \"{}\" in {}",
+                    key,
+                    profile,
+                    path.display()
+                ));
+            }
+        }
+        ConfigCommand::Get { key } => {
+            let value = file
+                .get(&profile, &key)?
+                .ok_or_else(|| anyhow::anyhow!("{} is not set", key))?;
+            println!("{}", value);
+        }
+        ConfigCommand::SetKey => {
+            let api_key = if std::io::stdin().is_terminal() {
+                Password::new().with_prompt("API Key").interact()?
+            } else {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)?;
+                line.trim().to_string()
+            };
+            if api_key.is_empty() {
+                return Err(anyhow::anyhow!("API key cannot be empty"));
+            }
+            store_api_key(&mut file, &profile, &api_key, out)?;
+        }
+        ConfigCommand::Show => {
+            let stored = file.profile(&profile).cloned().unwrap_or_default();
+            let (host, host_source) = match std::env::var("N8N_HOST") {
+                Ok(v) if use_env => (Some(v), Some("env")),
+                _ => (stored.host.clone(), stored.host.as_ref().map(|_| "file")),
+            };
+            let secrets = DefaultStore::from_env()?;
+            let (api_key, key_source) =
+                match config::resolve_api_key(&profile, &stored, &secrets, use_env) {
+                    Ok(Some((key, source))) => (Some(key), Some(source.as_str())),
+                    Ok(None) => (None, None),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "could not resolve the API key");
+                        (None, None)
+                    }
+                };
+            out.emit(&ConfigReport {
+                path: config::ConfigFile::path()?.display().to_string(),
+                profile: profile.clone(),
+                entries: vec![
+                    ConfigEntry {
+                        key: "host".into(),
+                        value: host,
+                        source: host_source,
+                    },
+                    ConfigEntry {
+                        key: "api_key_command".into(),
+                        value: stored.api_key_command.clone(),
+                        source: stored.api_key_command.as_ref().map(|_| "file"),
+                    },
+                    ConfigEntry {
+                        key: "commit_message".into(),
+                        value: Some(file.commit_message(&profile)),
+                        source: Some(if stored.commit_message.is_some() {
+                            "file"
+                        } else {
+                            "default"
+                        }),
+                    },
+                    ConfigEntry {
+                        key: "node_source".into(),
+                        value: Some(file.node_source(&profile)?.to_string()),
+                        source: Some(if stored.node_source.is_some() {
+                            "file"
+                        } else {
+                            "default"
+                        }),
+                    },
+                    ConfigEntry {
+                        key: "api_key".into(),
+                        value: api_key.map(|k| config::redact(&k)),
+                        source: key_source,
+                    },
+                ],
+            })?;
+        }
+        ConfigCommand::Profiles => {
+            let profiles = file
+                .profile_names()
+                .into_iter()
+                .map(|name| ProfileSummary {
+                    active: name == profile,
+                    host: file.profile(&name).and_then(|p| p.host.clone()),
+                    name,
+                })
+                .collect();
+            out.emit(&ProfilesReport { profiles })?;
+        }
+    }
+    Ok(())
+}
+
+/// Process exit code for a failed command, distinguishing API failures.
+fn exit_code(err: &anyhow::Error) -> u8 {
+    let Some(api_err) = err.chain().find_map(|e| e.downcast_ref::<ApiError>()) else {
+        return 1;
+    };
+    match api_err {
+        ApiError::Unauthorized => 3,
+        ApiError::Forbidden => 4,
+        ApiError::NotFound { .. } => 5,
+        ApiError::Conflict { .. } => 6,
+        ApiError::Validation { .. } => 7,
+        ApiError::RateLimited { .. } => 8,
+        ApiError::Server { .. } | ApiError::RepeatedCursor { .. } => 9,
+        ApiError::Transport(_) => 10,
+        ApiError::Decode(_) => 11,
+    }
+}
+
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
 
-    // Validate environment variables early with helpful error messages
-    let cfg = config::N8nConfig::from_env().with_context(|| {
-        "Failed to load configuration. Please ensure N8N_HOST and N8N_API_KEY environment variables are set.\n\
+/// Create a client for the selected profile.
+fn connect(cli: &Cli) -> anyhow::Result<api::N8nClient> {
+    // Validate configuration early with helpful error messages
+    let cfg = config::N8nConfig::load(cli.profile.as_deref()).with_context(|| {
+        "Failed to load configuration. Run `n8n-workflow-sync config init` or set the N8N_HOST and N8N_API_KEY environment variables.\n\
         Example:\n  \
         export N8N_HOST=https://your-n8n.example.com\n  \
         export N8N_API_KEY=your-api-key-here"
     })?;
+    Ok(api::N8nClient::new(cfg)?.with_retry_policy(RetryPolicy {
+        max_retries: cli.retries,
+        ..RetryPolicy::default()
+    }))
+}
+
+/// Report outdated, deprecated and unknown nodes. Only connects to read the
+/// configured node source when a workflow has no `node-versions.json`.
+async fn run_outdated(cli: &Cli, path: &Path, check: bool, out: &Output) -> anyhow::Result<()> {
+    let mut catalogues = std::collections::HashMap::new();
+    let mut checked = 0;
+    let mut found = Vec::new();
+    for file in outdated::workflow_files(path)? {
+        let workflow = read_workflow(&file)?;
+        let versions_file = outdated::find_node_versions(&file);
+        if !catalogues.contains_key(&versions_file) {
+            let catalogue = match &versions_file {
+                Some(versions_file) => nodes::load_node_versions(versions_file)?,
+                None => {
+                    let client = connect(cli)?;
+                    let config_file = config::ConfigFile::load()?;
+                    let source = config_file
+                        .node_source(&config_file.active_profile(cli.profile.as_deref()))?;
+                    out.info(format!(
+                        "No {} found, reading node versions from {}...",
+                        nodes::NODE_VERSIONS_FILE,
+                        source
+                    ));
+                    nodes::node_versions(
+                        client.http(),
+                        &client.config().host,
+                        &source,
+                        &nodes::NodeCache::open()?,
+                        false,
+                    )
+                    .await?
+                    .nodes
+                }
+            };
+            catalogues.insert(versions_file.clone(), catalogue);
+        }
+        checked += workflow
+            .get("nodes")
+            .and_then(|n| n.as_array())
+            .map_or(0, Vec::len);
+        found.extend(outdated::check(&file, &workflow, &catalogues[&versions_file]));
+    }
+
+    let failing = found.iter().filter(|n| n.state.fails_check()).count();
+    out.emit(&OutdatedReport {
+        checked,
+        nodes: found,
+    })?;
+    if check && failing > 0 {
+        anyhow::bail!("{} of {} nodes are outdated or deprecated", failing, checked);
+    }
+    Ok(())
+}
+
+async fn run() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "warn".into()),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+    let out = Output::new(cli.output);
+
+    if let Commands::Config { action } = cli.command {
+        return run_config(action, cli.profile.as_deref(), &out);
+    }
+
+    // First run: offer guided setup when nothing is configured yet
+    let env_configured = std::env::var_os("N8N_HOST").is_some()
+        && (std::env::var_os("N8N_API_KEY").is_some()
+            || std::env::var_os(config::API_KEY_FILE_ENV).is_some());
+    if !env_configured && !config::ConfigFile::path()?.exists() && std::io::stdin().is_terminal()
+    {
+        out.info("No configuration found. Let's set up your n8n connection.");
+        setup_wizard(&out, cli.profile.as_deref().unwrap_or(config::DEFAULT_PROFILE))?;
+    }
+
+    if let Commands::Outdated { path, check } = &cli.command {
+        return run_outdated(&cli, path, *check, &out).await;
+    }
+
+    let client = connect(&cli)?;
+    let config_file = config::ConfigFile::load()?;
+    let active_profile = config_file.active_profile(cli.profile.as_deref());
+    let commit_template = config_file.commit_message(&active_profile);
+    let node_source = config_file.node_source(&active_profile)?;
 
     match cli.command {
-        Commands::List => {
-            println!("Fetching workflows from {}...", cfg.host);
-            let workflows = api::list_workflows(&cfg).await.with_context(
+        Commands::List { filter } => {
+            out.info(format!("Fetching workflows from {}...", client.config().host));
+            let workflows = client.list_workflows(&filter.to_filter(), filter.limit).await.with_context(
                 || "Failed to list workflows. Please check your N8N_HOST and N8N_API_KEY",
             )?;
 
-            if workflows.is_empty() {
-                println!("No workflows found on the server.");
-            } else {
-                println!("Found {} workflows:", workflows.len());
-                for wf in workflows {
-                    println!("  {}: {}", wf.id, wf.name);
-                }
-            }
+            out.emit(&ListReport {
+                total: workflows.len(),
+                workflows: workflows.into_iter().map(Into::into).collect(),
+            })?;
         }
         Commands::New { name } => {
             if name.trim().is_empty() {
                 return Err(anyhow::anyhow!("Workflow name cannot be empty"));
             }
 
-            println!("Creating new workflow: \"{}\"", name);
-            let wf = api::create_workflow(&cfg, &name)
+            out.info(format!("Creating new workflow: \"{}\"", name));
+            let wf = client.create_workflow(&name)
                 .await
                 .with_context(|| format!("Failed to create workflow \"{}\"", name))?;
 
-            println!("Created workflow with ID: {}", wf.id);
+            out.info(format!("Created workflow with ID: {}", wf.id));
 
-            let wf_json = api::get_workflow(&cfg, &wf.id)
+            let wf_json = client.get_workflow(&wf.id)
                 .await
                 .with_context(|| format!("Failed to download workflow {}", wf.id))?;
 
@@ -178,62 +666,95 @@ async fn main() -> anyhow::Result<()> {
             fs::create_dir_all(&dir)
                 .with_context(|| format!("Failed to create directory {}", dir.display()))?;
 
-            let json_path = dir.join("workflow.json");
-            let data = serde_json::to_vec_pretty(&wf_json)?;
-            fs::write(&json_path, data)
+            let json_path = layout::workflow_path(&dir, None)?;
+            layout::save(&json_path, &wf_json, None)
                 .with_context(|| format!("Failed to write workflow to {}", json_path.display()))?;
+            lock::record(&json_path, LockEntry::from_remote(&wf.id, &wf_json))?;
 
-            nodes::save_node_versions(&dir)
-                .await
-                .with_context(|| "Failed to fetch node versions")?;
+            write_node_versions(&client, &node_source, &dir).await;
 
-            // Initialize git repository
-            let repo = Repository::init(&dir).with_context(|| {
-                format!("Failed to initialize git repository in {}", dir.display())
-            })?;
-            let mut index = repo.index()?;
-            index.add_path(Path::new("workflow.json"))?;
-            index.write()?;
-            let tree_id = index.write_tree()?;
-            let tree = repo.find_tree(tree_id)?;
-            let sig = Signature::now("n8n-workflow-sync", "n8n@localhost")?;
-            repo.commit(
-                Some("HEAD"),
-                &sig,
-                &sig,
-                &format!("feat: sync from n8n (workflow {})", wf.id),
-                &tree,
-                &[],
+            let (repo, _) = git::open_or_init(&dir)?;
+            let rel = json_path.strip_prefix(&dir).unwrap_or(&json_path);
+            let mut paths = layout::workflow_paths(rel);
+            paths.push(PathBuf::from(LOCK_FILE));
+            git::commit_paths(
+                &repo,
+                &paths.iter().map(PathBuf::as_path).collect::<Vec<_>>(),
+                &git::commit_message(&commit_template, &wf_json),
+                Some(&wf_json),
             )?;
 
-            println!(
-                "✓ Created workflow {} in directory: {}",
-                wf.id,
+            out.emit(&NewReport::new(&wf.id, &wf.name, &dir, &json_path))?;
+        }
+        Commands::Pull {
+            all: Some(dir),
+            concurrency,
+            format,
+            layout,
+            filter,
+            ..
+        } => {
+            out.info(format!(
+                "Downloading workflows from {} into {}...",
+                client.config().host,
                 dir.display()
-            );
-            println!("✓ Initialized git repository with initial commit");
+            ));
+            let summary = mirror::pull_all(
+                &client,
+                &dir,
+                &MirrorOptions {
+                    filter: filter.to_filter(),
+                    limit: filter.limit,
+                    concurrency,
+                    format,
+                    layout,
+                },
+            )
+            .await?;
+
+            write_node_versions(&client, &node_source, &dir).await;
+
+            out.emit(&PullAllReport {
+                directory: dir.display().to_string(),
+                added: summary.count(FileStatus::Added),
+                changed: summary.count(FileStatus::Changed),
+                unchanged: summary.count(FileStatus::Unchanged),
+                removed: summary.count(FileStatus::Removed),
+                commit: summary.commit.map(|oid| oid.to_string()),
+                workflows: summary.workflows,
+            })?;
         }
-        Commands::Pull { id, path } => {
-            let wf_json = api::get_workflow(&cfg, &id)
+        Commands::Pull {
+            id,
+            path,
+            format,
+            layout,
+            ..
+        } => {
+            let id = id.expect("clap requires an ID without --all");
+            let wf_json = client.get_workflow(&id)
                 .await
                 .with_context(|| format!("Failed to download workflow {}", id))?;
 
             // Determine directory and file path
-            let mut dir = PathBuf::new();
-            let json_path = if let Some(p) = path {
-                if p.is_dir() || p.extension().is_none() {
-                    dir = p.clone();
-                    dir.join("workflow.json")
-                } else {
-                    if let Some(parent) = p.parent() {
-                        dir = parent.to_path_buf();
-                    }
-                    p
+            let (dir, json_path) = match path {
+                Some(p) if p.is_dir() || p.extension().is_none() => {
+                    let json_path = layout::workflow_path(&p, format)?;
+                    (p, json_path)
+                }
+                Some(p) => {
+                    let dir = p
+                        .parent()
+                        .filter(|d| !d.as_os_str().is_empty())
+                        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+                    (dir, p)
+                }
+                None => {
+                    let name = wf_json.get("name").and_then(|v| v.as_str()).unwrap_or(&id);
+                    let dir = PathBuf::from(slugify(name));
+                    let json_path = layout::workflow_path(&dir, format)?;
+                    (dir, json_path)
                 }
-            } else {
-                let name = wf_json.get("name").and_then(|v| v.as_str()).unwrap_or(&id);
-                dir = PathBuf::from(slugify(name));
-                dir.join("workflow.json")
             };
 
             if !dir.exists() {
@@ -241,56 +762,60 @@ async fn main() -> anyhow::Result<()> {
                     .with_context(|| format!("Failed to create directory {}", dir.display()))?;
             }
 
-            if json_path.exists() {
-                if !Confirm::new()
-                    .with_prompt(format!("Overwrite {}?", json_path.display()))
+            // A copy in the other format is replaced by the new file. Only ask
+            // when the local copy has edits that would be lost, not when just
+            // its format or layout changes.
+            let replaced = layout::superseded(&json_path);
+            let existing = replaced.as_ref().unwrap_or(&json_path);
+            if existing.exists()
+                && layout::differs(existing, &wf_json, None)?
+                && !Confirm::new()
+                    .with_prompt(format!("Overwrite {}?", existing.display()))
                     .default(false)
                     .interact()?
-                {
-                    println!("Aborted");
-                    return Ok(());
-                }
+            {
+                out.info("Aborted");
+                return Ok(());
             }
 
-            let data = serde_json::to_vec_pretty(&wf_json)?;
-            fs::write(&json_path, data)
-                .with_context(|| format!("Failed to write to {}", json_path.display()))?;
+            if let Some(old) = &replaced {
+                layout::remove(old)?;
+                lock::forget(old)?;
+            }
+            layout::save(&json_path, &wf_json, layout)?;
+            lock::record(&json_path, LockEntry::from_remote(&id, &wf_json))?;
 
-            nodes::save_node_versions(&dir)
-                .await
-                .with_context(|| "Failed to fetch node versions")?;
+            write_node_versions(&client, &node_source, &dir).await;
 
             // Initialise git repo if none exists
-            let repo = match Repository::open(&dir) {
-                Ok(r) => r,
-                Err(_) => {
-                    println!("Initializing git repository in {}...", dir.display());
-                    Repository::init(&dir).with_context(|| {
-                        format!("Failed to initialize git repository in {}", dir.display())
-                    })?
-                }
-            };
+            let (repo, created) = git::open_or_init(&dir)?;
+            if created {
+                out.info(format!("Initialized git repository in {}", dir.display()));
+            }
 
-            // Commit the workflow.json file
-            let mut index = repo.index()?;
+            // Commit the workflow file, unless nothing changed since HEAD
             let rel = json_path.strip_prefix(&dir).unwrap_or(&json_path);
-            index.add_path(rel)?;
-            index.write()?;
-            let tree_id = index.write_tree()?;
-            let tree = repo.find_tree(tree_id)?;
-            let sig = Signature::now("n8n-workflow-sync", "n8n@localhost")?;
-            repo.commit(
-                Some("HEAD"),
-                &sig,
-                &sig,
-                &format!("feat: sync from n8n (workflow {})", id),
-                &tree,
-                &[],
+            let mut paths = layout::workflow_paths(rel);
+            if let Some(old) = &replaced {
+                paths.extend(layout::workflow_paths(old.strip_prefix(&dir).unwrap_or(old)));
+            }
+            paths.push(PathBuf::from(LOCK_FILE));
+            let commit = git::commit_paths(
+                &repo,
+                &paths.iter().map(PathBuf::as_path).collect::<Vec<_>>(),
+                &git::commit_message(&commit_template, &wf_json),
+                Some(&wf_json),
             )?;
 
-            println!("✓ Downloaded workflow {} to {}", id, json_path.display());
+            let name = wf_json.get("name").and_then(|v| v.as_str()).unwrap_or_default();
+            out.emit(&PullReport {
+                id: id.clone(),
+                name: name.to_string(),
+                path: json_path.display().to_string(),
+                commit: commit.map(|oid| oid.to_string()),
+            })?;
         }
-        Commands::Push { id, path } => {
+        Commands::Push { id, path, force } => {
             // Determine the path to use. If none provided, try common defaults.
             let path = match path {
                 Some(p) => p,
@@ -299,10 +824,7 @@ async fn main() -> anyhow::Result<()> {
                 )?,
             };
 
-            let data = fs::read_to_string(&path)
-                .with_context(|| format!("Failed to read {}", path.display()))?;
-            let json: serde_json::Value = serde_json::from_str(&data)
-                .with_context(|| format!("Failed to parse JSON in {}", path.display()))?;
+            let json = read_workflow(&path)?;
 
             // Determine workflow ID. Command line argument overrides JSON field.
             let id = match id {
@@ -316,16 +838,139 @@ async fn main() -> anyhow::Result<()> {
                     })?,
             };
 
-            println!("Uploading {} to workflow {}...", path.display(), id);
+            if !force {
+                match lock::entry_for(&path)? {
+                    Some(entry) if entry.id == id => {
+                        let lost = sync::remote_changes_since(&client, &id, &entry).await?;
+                        if !lost.is_empty() {
+                            out.info(format!(
+                                "Workflow {} was changed on the server since it was pulled. Pushing would overwrite:",
+                                id
+                            ));
+                            for change in &lost {
+                                out.info(format!("  {}", describe_change(change)));
+                            }
+                            return Err(ApiError::Conflict {
+                                message: format!(
+                                    "workflow {} changed on the server; run `sync` to merge or push with --force to overwrite",
+                                    id
+                                ),
+                            }
+                            .into());
+                        }
+                    }
+                    _ => tracing::warn!(
+                        path = %path.display(),
+                        "no pull recorded for this file, cannot check for remote changes"
+                    ),
+                }
+            }
+
+            out.info(format!("Uploading {} to workflow {}...", path.display(), id));
 
             let body = sanitize_for_update(&json);
-            let wf = api::update_workflow(&cfg, &id, &body)
+            let wf = client.update_workflow(&id, &body)
                 .await
                 .with_context(|| format!("Failed to update workflow {}", id))?;
-            println!("✓ Updated workflow {}: {}", wf.id, wf.name);
+            lock::record(
+                &path,
+                LockEntry {
+                    id: wf.id.clone(),
+                    updated_at: wf.updated_at.clone(),
+                    version_id: wf.version_id.clone(),
+                    base: body,
+                },
+            )?;
+            out.emit(&PushReport {
+                id: wf.id,
+                name: wf.name,
+                path: path.display().to_string(),
+            })?;
+        }
+        Commands::Sync { id, path } => {
+            let path = match path {
+                Some(p) => p,
+                None => default_json_path().with_context(
+                    || "Unable to determine workflow JSON file. Please specify a path.",
+                )?,
+            };
+
+            out.info(format!("Syncing {} with {}...", path.display(), client.config().host));
+            let outcome = sync::sync_workflow(&client, &path, id.as_deref(), &commit_template).await?;
+            let conflicts = outcome.conflicts.len();
+            out.emit(&SyncReport::new(outcome, &path))?;
+            if conflicts > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} conflicting change(s); edit {} to resolve them or use pull/push to pick a side",
+                    conflicts,
+                    path.display()
+                ));
+            }
+        }
+        Commands::Diff { id, path, json } => {
+            let path = match path {
+                Some(p) => p,
+                None => default_json_path().with_context(
+                    || "Unable to determine workflow JSON file. Please specify a path.",
+                )?,
+            };
+            let local = read_workflow(&path)?;
+            let id = lock::workflow_id(id.as_deref(), &path, &local)?;
+
+            let remote = client
+                .get_workflow(&id)
+                .await
+                .with_context(|| format!("Failed to download workflow {}", id))?;
+            let (remote, local) = (sanitize_for_update(&remote), sanitize_for_update(&local));
+
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&diff::json_patch(&remote, &local))?
+                );
+            } else {
+                out.emit(&DiffReport {
+                    id,
+                    path: path.display().to_string(),
+                    diff: diff::WorkflowDiff::between(&remote, &local),
+                })?;
+            }
         }
+        Commands::Status { dir } => {
+            out.info(format!(
+                "Comparing workflows in {} with {}...",
+                dir.display(),
+                client.config().host
+            ));
+            let workflows = status::scan(&client, &dir).await?;
+            out.emit(&StatusReport { workflows })?;
+        }
+        Commands::Nodes {
+            action: NodesCommand::Refresh,
+        } => {
+            let cache = nodes::NodeCache::open()?;
+            out.info(format!("Fetching node versions from {}...", node_source));
+            let versions = nodes::node_versions(
+                client.http(),
+                &client.config().host,
+                &node_source,
+                &cache,
+                true,
+            )
+            .await?;
+            out.emit(&NodesRefreshReport {
+                cache: cache
+                    .file_for(&versions.source)
+                    .map(|p| p.display().to_string()),
+                nodes: versions.nodes.len(),
+                source: versions.source.to_string(),
+                status: versions.status,
+            })?;
+        }
+        Commands::Config { .. } => unreachable!("config is handled before connecting"),
+        Commands::Outdated { .. } => unreachable!("outdated is handled before connecting"),
         Commands::Upgrade => {
-            println!("Checking for updates...");
+            out.info("Checking for updates...");
             self_update::backends::github::Update::configure()
                 .repo_owner("dunctk")
                 .repo_name("n8n-workflow-sync")
@@ -335,8 +980,60 @@ async fn main() -> anyhow::Result<()> {
                 .build()?
                 .update()
                 .with_context(|| "Failed to upgrade to latest release")?;
-            println!("✓ Updated to latest version");
+            out.info("✓ Updated to latest version");
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `outdated --check` on a workflow with the given nodes, checked
+    /// against a catalogue that only knows an up-to-date webhook node.
+    async fn check_nodes(nodes: serde_json::Value) -> anyhow::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(nodes::NODE_VERSIONS_FILE),
+            r#"{"n8n-nodes-base.webhook": {"type": "n8n-nodes-base.webhook", "versions": [1, 2]}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(WORKFLOW_FILE),
+            serde_json::to_vec(&serde_json::json!({ "nodes": nodes })).unwrap(),
+        )
+        .unwrap();
+        let cli = Cli::parse_from([
+            "n8n-workflow-sync",
+            "outdated",
+            dir.path().to_str().unwrap(),
+            "--check",
+        ]);
+        run_outdated(&cli, dir.path(), true, &Output::new(OutputFormat::Json)).await
+    }
+
+    #[tokio::test]
+    async fn check_ignores_unknown_nodes_but_fails_on_outdated_ones() {
+        let webhook = |version| {
+            serde_json::json!({
+                "name": "Webhook",
+                "type": "n8n-nodes-base.webhook",
+                "typeVersion": version
+            })
+        };
+        let community = serde_json::json!({
+            "name": "Widget",
+            "type": "n8n-nodes-acme.widget",
+            "typeVersion": 1
+        });
+
+        check_nodes(serde_json::json!([webhook(2), community.clone()]))
+            .await
+            .unwrap();
+        let err = check_nodes(serde_json::json!([webhook(1), community]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "1 of 2 nodes are outdated or deprecated");
+    }
+}
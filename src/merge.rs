@@ -0,0 +1,351 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::workflow::{find_node, node_key, node_label};
+
+/// A change made on both sides that could not be reconciled automatically.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Conflict {
+    /// Location of the conflicting value, e.g. `nodes["HTTP Request"].parameters.url`.
+    pub path: String,
+    pub base: Option<Value>,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    /// The merged workflow. Conflicting values keep the local side.
+    pub merged: Value,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeOutcome {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Three-way merge of two edited copies of a workflow against their common
+/// ancestor.
+///
+/// Objects are merged key by key and the `nodes` array is matched by node
+/// `id` (falling back to `name`), so edits to different nodes or different
+/// parameters of the same node combine cleanly. Without a `base` every
+/// difference between the two sides is reported as a conflict.
+///
+/// Because nodes are matched by `id` while `connections` refer to node
+/// names, the result is checked as a whole: duplicate node names and
+/// connections from or to a node that does not exist are conflicts too.
+pub fn three_way(base: Option<&Value>, local: &Value, remote: &Value) -> MergeOutcome {
+    let mut merger = Merger {
+        has_base: base.is_some(),
+        conflicts: Vec::new(),
+    };
+    let merged = merger
+        .value("", base, Some(local), Some(remote))
+        .unwrap_or(Value::Object(Map::new()));
+    let mut conflicts = merger.conflicts;
+    conflicts.extend(integrity_conflicts(&merged, base, local, remote));
+    MergeOutcome { merged, conflicts }
+}
+
+fn node_names(workflow: &Value) -> Vec<&str> {
+    workflow
+        .get("nodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|n| n.get("name").and_then(Value::as_str))
+        .collect()
+}
+
+/// Nodes named by the connections leaving one source node.
+fn connection_targets(outputs: &Value) -> impl Iterator<Item = &str> {
+    outputs
+        .as_object()
+        .into_iter()
+        .flat_map(Map::values)
+        .filter_map(Value::as_array)
+        .flatten()
+        .filter_map(Value::as_array)
+        .flatten()
+        .filter_map(|c| c.get("node").and_then(Value::as_str))
+}
+
+/// Problems n8n would reject in a merged workflow that no single field
+/// conflict shows: node names used twice, and connections whose source or
+/// target is not one of the merged nodes.
+fn integrity_conflicts(
+    merged: &Value,
+    base: Option<&Value>,
+    local: &Value,
+    remote: &Value,
+) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let names = node_names(merged);
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for name in &names {
+        *counts.entry(name).or_default() += 1;
+    }
+    let named = |side: &Value, name: &str| {
+        side.get("nodes")
+            .and_then(Value::as_array)
+            .and_then(|nodes| {
+                nodes
+                    .iter()
+                    .find(|n| n.get("name").and_then(Value::as_str) == Some(name))
+            })
+            .cloned()
+    };
+    for (name, _) in counts.iter().filter(|(_, n)| **n > 1) {
+        conflicts.push(Conflict {
+            path: format!("nodes[{:?}]", name),
+            base: base.and_then(|b| named(b, name)),
+            local: named(local, name),
+            remote: named(remote, name),
+        });
+    }
+
+    let known: BTreeSet<&str> = names.into_iter().collect();
+    let Some(connections) = merged.get("connections").and_then(Value::as_object) else {
+        return conflicts;
+    };
+    for (source, outputs) in connections {
+        let dangling = !known.contains(source.as_str())
+            || connection_targets(outputs).any(|t| !known.contains(t));
+        if dangling {
+            let side = |w: &Value| w.get("connections").and_then(|c| c.get(source)).cloned();
+            conflicts.push(Conflict {
+                path: format!("connections[{:?}]", source),
+                base: base.and_then(side),
+                local: side(local),
+                remote: side(remote),
+            });
+        }
+    }
+    conflicts
+}
+
+struct Merger {
+    /// Whether a common ancestor exists; without one a value missing from
+    /// the base cannot be told apart from one nobody touched.
+    has_base: bool,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger {
+    fn value(
+        &mut self,
+        path: &str,
+        base: Option<&Value>,
+        local: Option<&Value>,
+        remote: Option<&Value>,
+    ) -> Option<Value> {
+        if local == remote {
+            return local.cloned();
+        }
+        if self.has_base && base == local {
+            return remote.cloned();
+        }
+        if self.has_base && base == remote {
+            return local.cloned();
+        }
+
+        match (base, local, remote) {
+            (base, Some(Value::Object(l)), Some(Value::Object(r)))
+                if base.is_none_or(Value::is_object) =>
+            {
+                let b = base.and_then(Value::as_object);
+                Some(Value::Object(self.object(path, b, l, r)))
+            }
+            (base, Some(Value::Array(l)), Some(Value::Array(r)))
+                if path == "nodes" && base.is_none_or(Value::is_array) =>
+            {
+                let b = base.and_then(Value::as_array).map(Vec::as_slice);
+                Some(Value::Array(self.nodes(b, l, r)))
+            }
+            _ => {
+                self.conflicts.push(Conflict {
+                    path: path.to_string(),
+                    base: base.cloned(),
+                    local: local.cloned(),
+                    remote: remote.cloned(),
+                });
+                local.cloned()
+            }
+        }
+    }
+
+    fn object(
+        &mut self,
+        path: &str,
+        base: Option<&Map<String, Value>>,
+        local: &Map<String, Value>,
+        remote: &Map<String, Value>,
+    ) -> Map<String, Value> {
+        let mut keys: Vec<&String> = local.keys().collect();
+        keys.extend(remote.keys().filter(|k| !local.contains_key(*k)));
+
+        let mut out = Map::new();
+        for key in keys {
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            let merged = self.value(
+                &child,
+                base.and_then(|b| b.get(key)),
+                local.get(key),
+                remote.get(key),
+            );
+            if let Some(v) = merged {
+                out.insert(key.clone(), v);
+            }
+        }
+        out
+    }
+
+    /// Merge node lists, keeping local order and appending nodes added remotely.
+    fn nodes(&mut self, base: Option<&[Value]>, local: &[Value], remote: &[Value]) -> Vec<Value> {
+        let mut ordered: Vec<&Value> = local.iter().collect();
+        ordered.extend(
+            remote
+                .iter()
+                .filter(|n| find_node(Some(local), node_key(n)).is_none()),
+        );
+
+        let mut out = Vec::new();
+        for node in ordered {
+            let key = node_key(node);
+            let merged = self.value(
+                &node_label(node),
+                find_node(base, key),
+                find_node(Some(local), key),
+                find_node(Some(remote), key),
+            );
+            out.extend(merged);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn node(id: &str, name: &str, url: &str) -> Value {
+        json!({"id": id, "name": name, "type": "n8n-nodes-base.httpRequest", "parameters": {"url": url}})
+    }
+
+    #[test]
+    fn combines_edits_to_different_nodes() {
+        let base = json!({"name": "Flow", "nodes": [node("a", "A", "x"), node("b", "B", "y")]});
+        let local =
+            json!({"name": "Flow", "nodes": [node("a", "A", "local"), node("b", "B", "y")]});
+        let remote =
+            json!({"name": "Renamed", "nodes": [node("a", "A", "x"), node("b", "B", "remote")]});
+
+        let outcome = three_way(Some(&base), &local, &remote);
+        assert!(outcome.is_clean());
+        assert_eq!(
+            outcome.merged,
+            json!({"name": "Renamed", "nodes": [node("a", "A", "local"), node("b", "B", "remote")]})
+        );
+    }
+
+    #[test]
+    fn applies_additions_and_deletions_from_both_sides() {
+        let base = json!({"nodes": [node("a", "A", "x"), node("b", "B", "y")]});
+        let local = json!({"nodes": [node("a", "A", "x"), node("c", "C", "z")]});
+        let remote = json!({"nodes": [node("b", "B", "y"), node("d", "D", "w")]});
+
+        let outcome = three_way(Some(&base), &local, &remote);
+        assert!(outcome.is_clean());
+        assert_eq!(
+            outcome.merged,
+            json!({"nodes": [node("c", "C", "z"), node("d", "D", "w")]})
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_parameter_edits() {
+        let base = json!({"nodes": [node("a", "HTTP Request", "x")]});
+        let local = json!({"nodes": [node("a", "HTTP Request", "local")]});
+        let remote = json!({"nodes": [node("a", "HTTP Request", "remote")]});
+
+        let outcome = three_way(Some(&base), &local, &remote);
+        assert_eq!(outcome.conflicts.len(), 1);
+        let conflict = &outcome.conflicts[0];
+        assert_eq!(conflict.path, "nodes[\"HTTP Request\"].parameters.url");
+        assert_eq!(conflict.local, Some(json!("local")));
+        assert_eq!(conflict.remote, Some(json!("remote")));
+    }
+
+    #[test]
+    fn deleting_a_node_edited_elsewhere_conflicts() {
+        let base = json!({"nodes": [node("a", "A", "x")]});
+        let local = json!({"nodes": []});
+        let remote = json!({"nodes": [node("a", "A", "changed")]});
+
+        let outcome = three_way(Some(&base), &local, &remote);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].path, "nodes[\"A\"]");
+        assert_eq!(outcome.conflicts[0].local, None);
+    }
+
+    fn edge(to: &str) -> Value {
+        json!({"main": [[{"node": to, "type": "main", "index": 0}]]})
+    }
+
+    #[test]
+    fn connections_from_a_renamed_node_conflict() {
+        let base = json!({"nodes": [node("a", "A", "x"), node("c", "C", "y")], "connections": {}});
+        let local = json!({"nodes": [node("a", "B", "x"), node("c", "C", "y")], "connections": {}});
+        let remote = json!({
+            "nodes": [node("a", "A", "x"), node("c", "C", "y")],
+            "connections": {"A": edge("C")}
+        });
+
+        let outcome = three_way(Some(&base), &local, &remote);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].path, "connections[\"A\"]");
+        assert_eq!(outcome.conflicts[0].remote, Some(edge("C")));
+
+        // An edge to the old name is caught as well.
+        let remote = json!({
+            "nodes": [node("a", "A", "x"), node("c", "C", "y")],
+            "connections": {"C": edge("A")}
+        });
+        let outcome = three_way(Some(&base), &local, &remote);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].path, "connections[\"C\"]");
+    }
+
+    #[test]
+    fn nodes_added_with_the_same_name_conflict() {
+        let base = json!({"nodes": [node("a", "A", "x")]});
+        let local = json!({"nodes": [node("a", "A", "x"), node("l", "Fetch", "local")]});
+        let remote = json!({"nodes": [node("a", "A", "x"), node("r", "Fetch", "remote")]});
+
+        let outcome = three_way(Some(&base), &local, &remote);
+        assert_eq!(outcome.conflicts.len(), 1);
+        let conflict = &outcome.conflicts[0];
+        assert_eq!(conflict.path, "nodes[\"Fetch\"]");
+        assert_eq!(conflict.local, Some(node("l", "Fetch", "local")));
+        assert_eq!(conflict.remote, Some(node("r", "Fetch", "remote")));
+    }
+
+    #[test]
+    fn without_base_only_identical_values_merge() {
+        let local = json!({"name": "Flow", "settings": {"timezone": "UTC"}});
+        let remote = json!({"name": "Flow", "settings": {"timezone": "CET"}});
+
+        let outcome = three_way(None, &local, &remote);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].path, "settings.timezone");
+    }
+}
@@ -0,0 +1,435 @@
+use anyhow::Context;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::api::{ListFilter, N8nClient, Workflow};
+use crate::error::ApiError;
+use crate::git;
+use crate::layout::{self, Layout};
+use crate::lock::{self, LOCK_FILE, LockEntry, SyncLock};
+use crate::workflow::{Format, slugify};
+
+/// Default number of workflows downloaded at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// What happened to a workflow file during [`pull_all`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Added,
+    Changed,
+    Unchanged,
+    /// The workflow no longer exists on the server and its files were deleted.
+    Removed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MirroredWorkflow {
+    pub id: String,
+    pub name: String,
+    /// Path of `workflow.json`, relative to the mirror directory.
+    pub path: PathBuf,
+    pub status: FileStatus,
+}
+
+#[derive(Debug)]
+pub struct MirrorSummary {
+    pub workflows: Vec<MirroredWorkflow>,
+    /// The commit recording this pull, or `None` if nothing changed.
+    pub commit: Option<git2::Oid>,
+}
+
+impl MirrorSummary {
+    pub fn count(&self, status: FileStatus) -> usize {
+        self.workflows.iter().filter(|w| w.status == status).count()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MirrorOptions {
+    pub filter: ListFilter,
+    pub limit: u32,
+    pub concurrency: usize,
+    /// Format to write workflows in, `None` to keep the one already on disk.
+    pub format: Option<Format>,
+    /// Layout to write workflows in, `None` to keep the one already on disk.
+    pub layout: Option<Layout>,
+}
+
+impl Default for MirrorOptions {
+    fn default() -> Self {
+        Self {
+            filter: ListFilter::default(),
+            limit: crate::api::DEFAULT_PAGE_LIMIT,
+            concurrency: DEFAULT_CONCURRENCY,
+            format: None,
+            layout: None,
+        }
+    }
+}
+
+/// Workflows already mirrored into `dir`, keyed by id, with the path of their
+/// workflow file relative to `dir` as recorded in the lock files.
+fn known_workflows(dir: &Path) -> anyhow::Result<HashMap<String, (PathBuf, LockEntry)>> {
+    let mut known = HashMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(known);
+    };
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() || !SyncLock::path(&entry.path()).is_file() {
+            continue;
+        }
+        let name = PathBuf::from(entry.file_name());
+        for (file, lock) in SyncLock::load(&entry.path())?.workflows {
+            known.insert(lock.id.clone(), (name.join(file), lock));
+        }
+    }
+    Ok(known)
+}
+
+/// Pick a directory name per workflow. Workflows pulled before keep their
+/// directory, so names do not depend on renames or on the current filters.
+/// New workflows get their slug, with the id appended when the slug is
+/// shared with another new workflow or already used by a known one.
+fn directory_names(workflows: &[Workflow], known: &HashMap<String, PathBuf>) -> Vec<String> {
+    let taken: HashSet<String> = known
+        .values()
+        .filter_map(|path| path.parent())
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .collect();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for wf in workflows.iter().filter(|wf| !known.contains_key(&wf.id)) {
+        *counts.entry(slugify(&wf.name)).or_default() += 1;
+    }
+    workflows
+        .iter()
+        .map(|wf| {
+            if let Some(dir) = known.get(&wf.id).and_then(|path| path.parent()) {
+                return dir.to_string_lossy().into_owned();
+            }
+            let slug = slugify(&wf.name);
+            if slug.is_empty() {
+                wf.id.clone()
+            } else if counts[&slug] > 1 || taken.contains(&slug) {
+                format!("{}-{}", slug, wf.id)
+            } else {
+                slug
+            }
+        })
+        .collect()
+}
+
+/// Delete the files of a mirrored workflow that was removed from the server,
+/// along with its lock file and directory once nothing else is left in them.
+fn remove_workflow(dir: &Path, rel: &Path) -> anyhow::Result<()> {
+    let path = dir.join(rel);
+    layout::remove(&path)?;
+    let lock = lock::forget(&path)?;
+    let parent = lock.parent().unwrap_or(dir);
+    if SyncLock::load(parent)?.workflows.is_empty() {
+        fs::remove_file(&lock).with_context(|| format!("Failed to remove {}", lock.display()))?;
+        // Keep the directory if the user stored other files in it.
+        let _ = fs::remove_dir(parent);
+    }
+    Ok(())
+}
+
+/// Download every workflow matching the filter into `dir/<slug>/workflow.json`
+/// and record the result in a single commit of the git repository at `dir`.
+///
+/// Previously mirrored workflows that are missing from the listing are
+/// looked up individually; those the server no longer has are deleted and
+/// reported as [`FileStatus::Removed`].
+pub async fn pull_all(
+    client: &N8nClient,
+    dir: &Path,
+    opts: &MirrorOptions,
+) -> anyhow::Result<MirrorSummary> {
+    let workflows = client
+        .list_workflows(&opts.filter, opts.limit)
+        .await
+        .with_context(|| "Failed to list workflows")?;
+    let known = known_workflows(dir)?;
+    let known_paths = known
+        .iter()
+        .map(|(id, (path, _))| (id.clone(), path.clone()))
+        .collect();
+    let names = directory_names(&workflows, &known_paths);
+
+    let listed: HashSet<&str> = workflows.iter().map(|wf| wf.id.as_str()).collect();
+    let missing: Vec<_> = known
+        .into_iter()
+        .filter(|(id, _)| !listed.contains(id.as_str()))
+        .collect();
+    // The listing may be filtered, so only a 404 proves a workflow is gone.
+    let deleted: Vec<_> = stream::iter(missing)
+        .map(|(id, known)| async move {
+            match client.get_workflow(&id).await {
+                Ok(_) => Ok(None),
+                Err(ApiError::NotFound { .. }) => Ok(Some((id, known))),
+                Err(e) => Err(anyhow::Error::new(e))
+                    .with_context(|| format!("Failed to look up workflow {}", id)),
+            }
+        })
+        .buffer_unordered(opts.concurrency.max(1))
+        .try_filter_map(|found| async move { Ok(found) })
+        .try_collect()
+        .await?;
+
+    let downloads: Vec<_> = stream::iter(workflows.into_iter().zip(names))
+        .map(|(wf, name)| async move {
+            let json = client
+                .get_workflow(&wf.id)
+                .await
+                .with_context(|| format!("Failed to download workflow {}", wf.id))?;
+            anyhow::Ok((wf, name, json))
+        })
+        .buffer_unordered(opts.concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+
+    let mut mirrored = Vec::with_capacity(downloads.len());
+    // Workflow files replaced by a file in another format, relative to `dir`.
+    let mut replaced = Vec::new();
+    for (wf, name, json) in downloads {
+        let path = layout::workflow_path(&dir.join(&name), opts.format)?;
+        let rel = Path::new(&name).join(path.file_name().unwrap_or_default());
+        let mut existed = path.exists();
+        if let Some(old) = layout::superseded(&path) {
+            layout::remove(&old)?;
+            lock::forget(&old)?;
+            replaced.push(Path::new(&name).join(old.file_name().unwrap_or_default()));
+            existed = true;
+        }
+        fs::create_dir_all(dir.join(&name))?;
+        let status = match layout::save(&path, &json, opts.layout)? {
+            false => FileStatus::Unchanged,
+            true if existed => FileStatus::Changed,
+            true => FileStatus::Added,
+        };
+        lock::record(&path, LockEntry::from_remote(&wf.id, &json))?;
+        mirrored.push(MirroredWorkflow {
+            id: wf.id,
+            name: wf.name,
+            path: rel,
+            status,
+        });
+    }
+    for (id, (rel, entry)) in deleted {
+        remove_workflow(dir, &rel)?;
+        let name = entry
+            .base
+            .get("name")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        mirrored.push(MirroredWorkflow {
+            id,
+            name,
+            path: rel,
+            status: FileStatus::Removed,
+        });
+    }
+    mirrored.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut summary = MirrorSummary {
+        workflows: mirrored,
+        commit: None,
+    };
+    let mut message = format!(
+        "feat: sync {} workflows from n8n ({} added, {} changed",
+        summary.workflows.len() - summary.count(FileStatus::Removed),
+        summary.count(FileStatus::Added),
+        summary.count(FileStatus::Changed),
+    );
+    match summary.count(FileStatus::Removed) {
+        0 => message.push(')'),
+        removed => message.push_str(&format!(", {} removed)", removed)),
+    }
+    let (repo, _) = git::open_or_init(dir)?;
+    let tracked: Vec<PathBuf> = summary
+        .workflows
+        .iter()
+        .map(|w| w.path.as_path())
+        .chain(replaced.iter().map(PathBuf::as_path))
+        .flat_map(layout::workflow_paths)
+        .chain(
+            summary
+                .workflows
+                .iter()
+                .map(|w| w.path.with_file_name(LOCK_FILE)),
+        )
+        .collect();
+    let paths: Vec<&Path> = tracked.iter().map(PathBuf::as_path).collect();
+    summary.commit = git::commit_paths(&repo, &paths, &message, None)?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::N8nConfig;
+    use url::Url;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn wf(id: &str, name: &str) -> Workflow {
+        serde_json::from_value(serde_json::json!({"id": id, "name": name})).unwrap()
+    }
+
+    #[test]
+    fn duplicate_slugs_get_the_workflow_id() {
+        let names = directory_names(
+            &[
+                wf("1", "Billing"),
+                wf("2", "Sync"),
+                wf("3", "sync"),
+                wf("4", "!!!"),
+            ],
+            &HashMap::new(),
+        );
+        assert_eq!(names, ["billing", "sync-2", "sync-3", "4"]);
+    }
+
+    #[test]
+    fn known_workflows_keep_their_directory() {
+        let known = HashMap::from([
+            ("1".to_string(), PathBuf::from("billing/workflow.json")),
+            ("2".to_string(), PathBuf::from("old-name/workflow.yaml")),
+        ]);
+        let names = directory_names(
+            &[wf("1", "Billing"), wf("2", "New name"), wf("3", "Billing")],
+            &known,
+        );
+        assert_eq!(names, ["billing", "old-name", "billing-3"]);
+    }
+
+    async fn mock_instance(server: &MockServer, second_name: &str) {
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"id": "1", "name": "First"}, {"id": "2", "name": "Second"}]
+            })))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"id": "1", "name": "First", "nodes": []})),
+            )
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"id": "2", "name": "Second", "nodes": [], "note": second_name}),
+            ))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn pull_all_commits_once_and_tracks_changes() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let client = N8nClient::new(N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        })
+        .unwrap();
+
+        mock_instance(&server, "v1").await;
+        let first = pull_all(&client, dir.path(), &MirrorOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(first.count(FileStatus::Added), 2);
+        assert!(first.commit.is_some());
+        assert!(dir.path().join("first/workflow.json").exists());
+        assert!(dir.path().join("second/workflow.json").exists());
+
+        let again = pull_all(&client, dir.path(), &MirrorOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(again.count(FileStatus::Unchanged), 2);
+        assert!(again.commit.is_none());
+
+        mock_instance(&server, "v2").await;
+        let changed = pull_all(&client, dir.path(), &MirrorOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(changed.count(FileStatus::Changed), 1);
+        assert_eq!(changed.count(FileStatus::Unchanged), 1);
+
+        let repo = git2::Repository::open(dir.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 1);
+        assert_eq!(head.parent_id(0).unwrap(), first.commit.unwrap());
+    }
+
+    #[tokio::test]
+    async fn renamed_workflows_stay_and_deleted_ones_are_removed() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let client = N8nClient::new(N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        })
+        .unwrap();
+
+        mock_instance(&server, "v1").await;
+        pull_all(&client, dir.path(), &MirrorOptions::default())
+            .await
+            .unwrap();
+
+        // Workflow 1 is renamed to "Second", workflow 2 is deleted.
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"id": "1", "name": "Second"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"id": "1", "name": "Second", "nodes": []})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/2"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let summary = pull_all(&client, dir.path(), &MirrorOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(summary.count(FileStatus::Changed), 1);
+        assert_eq!(summary.count(FileStatus::Removed), 1);
+        let removed = summary
+            .workflows
+            .iter()
+            .find(|w| w.status == FileStatus::Removed)
+            .unwrap();
+        assert_eq!((removed.id.as_str(), removed.name.as_str()), ("2", "Second"));
+        assert!(dir.path().join("first/workflow.json").exists());
+        assert!(!dir.path().join("second").exists());
+
+        let repo = git2::Repository::open(dir.path()).unwrap();
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        assert!(tree.get_path(Path::new("first/workflow.json")).is_ok());
+        assert!(tree.get_path(Path::new("second")).is_err());
+    }
+}
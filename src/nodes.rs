@@ -1,10 +1,460 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt, TryStreamExt};
 use regex::Regex;
-use reqwest::Client;
-use serde::Deserialize;
-use std::collections::HashMap;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::{Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use url::Url;
+
+use crate::api::N8nClient;
+use crate::config::APP_NAME;
+
+/// File written next to pulled workflows with the latest node versions.
+pub const NODE_VERSIONS_FILE: &str = "node-versions.json";
+
+/// Environment variable overriding the directory node versions are cached in.
+pub const CACHE_DIR_ENV: &str = "N8N_SYNC_CACHE_DIR";
+
+/// How long node versions read from `master` are used without checking
+/// GitHub. Versions read from a tag never change and are cached for good.
+pub const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const GITHUB_API: &str = "https://api.github.com/repos/n8n-io/n8n";
+const GITHUB_RAW: &str = "https://raw.githubusercontent.com/n8n-io/n8n";
+const MASTER: &str = "master";
+
+/// Environment variable holding a GitHub token, used to raise the API rate
+/// limit when reading node sources from GitHub.
+pub const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+
+/// Default number of node sources downloaded from GitHub at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+
+/// npm package of the built-in nodes, which prefixes their type names.
+const NODES_BASE_PACKAGE: &str = "n8n-nodes-base";
+
+/// A package of nodes shipped with n8n.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct NodePackage {
+    /// npm package name, which prefixes the type names of its nodes.
+    name: &'static str,
+    /// Location of its node sources in the n8n repository.
+    dir: &'static str,
+}
+
+/// Every package node sources are read from.
+const NODE_PACKAGES: &[NodePackage] = &[
+    NodePackage {
+        name: NODES_BASE_PACKAGE,
+        dir: "packages/nodes-base/nodes",
+    },
+    NodePackage {
+        name: "@n8n/n8n-nodes-langchain",
+        dir: "packages/@n8n/nodes-langchain/nodes",
+    },
+];
+
+/// The package whose sources in the n8n repository contain `path`, and the
+/// path relative to its node directory.
+fn package_of(path: &str) -> Option<(NodePackage, &Path)> {
+    NODE_PACKAGES.iter().find_map(|package| {
+        Path::new(path)
+            .strip_prefix(package.dir)
+            .ok()
+            .map(|rel| (*package, rel))
+    })
+}
+
+/// A node version, such as `1`, `2` or `4.2`.
+#[derive(Debug, Clone, Copy)]
+pub struct Version(pub f64);
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for Version {
+    /// Whole versions are written as integers, like n8n writes `typeVersion`.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.fract() == 0.0 && self.0.abs() < 1e15 {
+            serializer.serialize_i64(self.0 as i64)
+        } else {
+            serializer.serialize_f64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Version)
+    }
+}
+
+/// What is known about a node type.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeDescriptor {
+    /// The type used in workflows, e.g. `n8n-nodes-base.httpRequest`.
+    #[serde(rename = "type")]
+    pub node_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Every supported version, in ascending order.
+    pub versions: Vec<Version>,
+    /// The version the editor adds new nodes with, when the node sets one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_version: Option<Version>,
+    /// Credential types the node can use.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credentials: Vec<String>,
+    /// Whether the node is hidden from the editor or marked deprecated.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+}
+
+impl NodeDescriptor {
+    /// The version new nodes get: `defaultVersion`, or else the highest
+    /// supported version.
+    pub fn latest(&self) -> Option<Version> {
+        self.default_version
+            .or_else(|| self.versions.last().copied())
+    }
+
+    /// Combine what `other` says about the same node, e.g. the base
+    /// description of a versioned node with one of its `V2/` descriptions.
+    fn merge(&mut self, other: NodeDescriptor) {
+        if self.node_type.is_empty() {
+            self.node_type = other.node_type;
+        }
+        self.display_name = self.display_name.take().or(other.display_name);
+        self.default_version = self.default_version.or(other.default_version);
+        self.versions.extend(other.versions);
+        self.versions.sort();
+        self.versions.dedup();
+        self.credentials.extend(other.credentials);
+        self.credentials.sort();
+        self.credentials.dedup();
+        self.deprecated |= other.deprecated;
+    }
+}
+
+/// Known node types, keyed by the type used in workflows, e.g.
+/// `n8n-nodes-base.httpRequest`.
+pub type Catalogue = BTreeMap<String, NodeDescriptor>;
+
+fn add_to_catalogue(catalogue: &mut Catalogue, node: NodeDescriptor) {
+    if node.node_type.is_empty() || node.versions.is_empty() {
+        return;
+    }
+    match catalogue.get_mut(&node.node_type) {
+        Some(known) => known.merge(node),
+        None => {
+            let mut node = node;
+            node.versions.sort();
+            node.versions.dedup();
+            catalogue.insert(node.node_type.clone(), node);
+        }
+    }
+}
+
+fn is_deprecated(display_name: Option<&str>, hidden: bool) -> bool {
+    hidden || display_name.is_some_and(|n| n.to_lowercase().contains("deprecated"))
+}
+
+/// The top-level items of the object or array literal whose opening bracket
+/// is at byte `open` of `text`, skipping strings and comments.
+fn literal_items(text: &str, open: usize) -> Option<Vec<&str>> {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut start = open + 1;
+    let mut items = vec![];
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'{' | b'[' | b'(' => depth += 1,
+            b'}' | b']' | b')' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    items.push(&text[start..i]);
+                    return Some(
+                        items
+                            .into_iter()
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .collect(),
+                    );
+                }
+            }
+            b',' if depth == 1 => {
+                items.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+static PROPERTY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)^(?:\s|//[^\n]*\n|/\*.*?\*/)*['"]?(\w+)['"]?\s*:\s*(.*)$"#).unwrap()
+});
+
+/// The `key: value` pairs among the items of an object literal.
+fn properties<'a>(items: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let caps = PROPERTY_RE.captures(item)?;
+            Some((caps.get(1)?.as_str(), caps.get(2)?.as_str().trim()))
+        })
+        .collect()
+}
+
+fn string_literal(value: &str) -> Option<String> {
+    let quote = value
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '\'' | '"' | '`'))?;
+    let inner = value.strip_prefix(quote)?.strip_suffix(quote)?;
+    Some(inner.replace(&format!("\\{}", quote), &quote.to_string()))
+}
+
+/// A version number, or every number in an array of them.
+fn version_literal(value: &str) -> Vec<Version> {
+    if value.starts_with('[') {
+        literal_items(value, 0)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|v| v.parse().ok().map(Version))
+            .collect()
+    } else {
+        value.parse().ok().map(Version).into_iter().collect()
+    }
+}
+
+/// Start of a node description object, e.g.
+/// `description: INodeTypeDescription = {`, `const baseDescription = {` or
+/// `this.description = {`.
+static DESCRIPTION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b\w*[dD]escription\s*(?::\s*\w+\s*)?=\s*\{").unwrap());
+
+/// What the source of a node (`*.node.ts`, or `*.node.js` once compiled)
+/// declares in the top level of its description objects. `node_type` is the
+/// bare node name, and is empty for the description of one version of a
+/// versioned node, which takes its name from the base description.
+fn parse_node_file(text: &str) -> Option<NodeDescriptor> {
+    let mut node = NodeDescriptor::default();
+    let mut hidden = false;
+    for m in DESCRIPTION_RE.find_iter(text) {
+        let Some(items) = literal_items(text, m.end() - 1) else {
+            continue;
+        };
+        for (key, value) in properties(&items) {
+            match key {
+                "name" if node.node_type.is_empty() => {
+                    node.node_type = string_literal(value).unwrap_or_default();
+                }
+                "displayName" if node.display_name.is_none() => {
+                    node.display_name = string_literal(value);
+                }
+                "version" => node.versions.extend(version_literal(value)),
+                "defaultVersion" => {
+                    node.default_version = node
+                        .default_version
+                        .or(version_literal(value).first().copied());
+                }
+                "credentials" if value.starts_with('[') => {
+                    for credential in literal_items(value, 0).unwrap_or_default() {
+                        let name = literal_items(credential, 0)
+                            .map(|c| properties(&c))
+                            .unwrap_or_default()
+                            .into_iter()
+                            .find(|(k, _)| *k == "name")
+                            .and_then(|(_, v)| string_literal(v));
+                        node.credentials.extend(name);
+                    }
+                }
+                "hidden" => hidden |= value == "true",
+                _ => {}
+            }
+        }
+    }
+    node.deprecated = is_deprecated(node.display_name.as_deref(), hidden);
+    node.versions.sort();
+    node.versions.dedup();
+    node.credentials.sort();
+    node.credentials.dedup();
+    (!node.node_type.is_empty() || !node.versions.is_empty()).then_some(node)
+}
+
+fn is_node_file(path: &str) -> bool {
+    path.ends_with(".node.ts") || path.ends_with(".node.js")
+}
+
+static VERSION_DIR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[vV]\d+$").unwrap());
+static VERSION_SUFFIX_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\w+?)V\d+$").unwrap());
+
+/// The node a source file belongs to: its path without versioned folders
+/// and version suffixes, so `HttpRequest/V3/HttpRequestV3.node.ts` belongs
+/// with `HttpRequest/HttpRequest.node.ts`.
+fn node_file_group(path: &Path) -> PathBuf {
+    let mut group: PathBuf = path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter(|c| !VERSION_DIR_RE.is_match(&c.as_os_str().to_string_lossy()))
+        .collect();
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = file
+        .trim_end_matches(".ts")
+        .trim_end_matches(".js")
+        .trim_end_matches(".node");
+    group.push(
+        VERSION_SUFFIX_RE
+            .captures(stem)
+            .map_or(stem, |c| c.get(1).map_or(stem, |m| m.as_str())),
+    );
+    group
+}
+
+/// Collects node source files into a [`Catalogue`] of built-in nodes.
+#[derive(Default)]
+struct CatalogueBuilder {
+    groups: BTreeMap<(NodePackage, PathBuf), NodeDescriptor>,
+}
+
+impl CatalogueBuilder {
+    /// Add the source of a node of `package`, at `path` relative to the
+    /// package's node directory.
+    fn add(&mut self, package: NodePackage, path: &Path, text: &str) {
+        if let Some(node) = parse_node_file(text) {
+            let key = (package, node_file_group(path));
+            match self.groups.get_mut(&key) {
+                Some(group) => group.merge(node),
+                None => {
+                    self.groups.insert(key, node);
+                }
+            }
+        }
+    }
+
+    fn build(self) -> Catalogue {
+        let mut catalogue = Catalogue::new();
+        for ((package, _), mut node) in self.groups {
+            if !node.node_type.is_empty() {
+                node.node_type = format!("{}.{}", package.name, node.node_type);
+                add_to_catalogue(&mut catalogue, node);
+            }
+        }
+        catalogue
+    }
+}
+
+/// Descriptors from node type descriptions as served by an instance at
+/// `types/nodes.json`: an array of objects with a `name` and a `version`
+/// that is either a number or an array of numbers. Versioned nodes appear
+/// once per version.
+fn catalogue_from_descriptions(types: &Value) -> Result<Catalogue> {
+    let types = types
+        .as_array()
+        .context("Expected an array of node type descriptions")?;
+    let mut catalogue = Catalogue::new();
+    for description in types {
+        let Some(name) = description.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let versions = match description.get("version") {
+            Some(Value::Array(all)) => all.iter().filter_map(Value::as_f64).map(Version).collect(),
+            Some(v) => v.as_f64().map(Version).into_iter().collect(),
+            None => vec![],
+        };
+        let display_name = description
+            .get("displayName")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let hidden = description.get("hidden").and_then(Value::as_bool) == Some(true)
+            || description.get("deprecated").and_then(Value::as_bool) == Some(true);
+        let credentials = description
+            .get("credentials")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c.get("name").or(Some(c)).and_then(Value::as_str))
+            .map(str::to_string)
+            .collect();
+        add_to_catalogue(
+            &mut catalogue,
+            NodeDescriptor {
+                node_type: name.to_string(),
+                deprecated: is_deprecated(display_name.as_deref(), hidden),
+                display_name,
+                versions,
+                default_version: description
+                    .get("defaultVersion")
+                    .and_then(Value::as_f64)
+                    .map(Version),
+                credentials,
+            },
+        );
+    }
+    Ok(catalogue)
+}
 
 #[derive(Deserialize)]
 struct Tree {
@@ -18,59 +468,1083 @@ struct Entry {
     entry_type: String,
 }
 
-/// Fetch the latest node versions from the n8n repository
-pub async fn fetch_node_versions() -> Result<HashMap<String, u32>> {
-    let client = Client::new();
-    let tree_url = "https://api.github.com/repos/n8n-io/n8n/git/trees/master?recursive=1";
-    let tree: Tree = client
-        .get(tree_url)
-        .header("User-Agent", "n8n-workflow-sync")
+/// The repository tree and its ETag.
+type TreeWithEtag = (Tree, Option<String>);
+
+/// GitHub refused a request because the API rate limit is used up.
+#[derive(Debug, Error)]
+pub struct RateLimitExceeded {
+    /// When the limit resets, in seconds since the epoch.
+    pub reset_at: Option<u64>,
+    /// Whether the request was made with a `GITHUB_TOKEN`.
+    pub authenticated: bool,
+}
+
+impl fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GitHub API rate limit exceeded")?;
+        if let Some(reset_at) = self.reset_at {
+            let minutes = reset_at.saturating_sub(now()).div_ceil(60);
+            write!(
+                f,
+                ", it resets in {} minute{} (at {:02}:{:02} UTC)",
+                minutes,
+                if minutes == 1 { "" } else { "s" },
+                reset_at % 86400 / 3600,
+                reset_at % 3600 / 60
+            )?;
+        }
+        if !self.authenticated {
+            write!(f, ". Set {} to raise the limit", GITHUB_TOKEN_ENV)?;
+        }
+        Ok(())
+    }
+}
+
+/// The n8n repository on GitHub, where node sources are read from when no
+/// other source is available.
+#[derive(Debug, Clone)]
+pub struct GitHubRepo {
+    api_url: String,
+    raw_url: String,
+    token: Option<String>,
+    concurrency: usize,
+}
+
+impl Default for GitHubRepo {
+    fn default() -> Self {
+        Self {
+            api_url: GITHUB_API.to_string(),
+            raw_url: GITHUB_RAW.to_string(),
+            token: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl GitHubRepo {
+    /// The n8n repository, authenticated with `GITHUB_TOKEN` when it is set.
+    pub fn from_env() -> Self {
+        Self {
+            token: std::env::var(GITHUB_TOKEN_ENV)
+                .ok()
+                .filter(|t| !t.trim().is_empty()),
+            ..Self::default()
+        }
+    }
+
+    /// Read the repository from other base URLs: `api_url` in place of
+    /// `https://api.github.com/repos/n8n-io/n8n` and `raw_url` in place of
+    /// `https://raw.githubusercontent.com/n8n-io/n8n`.
+    pub fn with_base_urls(
+        mut self,
+        api_url: impl Into<String>,
+        raw_url: impl Into<String>,
+    ) -> Self {
+        self.api_url = api_url.into().trim_end_matches('/').to_string();
+        self.raw_url = raw_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// How many node sources are downloaded at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    async fn get(&self, client: &Client, url: &str, etag: Option<&str>) -> Result<Response> {
+        let mut request = client.get(url);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        let exhausted = response
+            .headers()
+            .get(RATE_LIMIT_REMAINING)
+            .is_some_and(|v| v.as_bytes() == b"0");
+        if exhausted
+            && matches!(
+                response.status(),
+                StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+            )
+        {
+            return Err(RateLimitExceeded {
+                reset_at: response
+                    .headers()
+                    .get(RATE_LIMIT_RESET)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok()),
+                authenticated: self.token.is_some(),
+            }
+            .into());
+        }
+        Ok(response)
+    }
+
+    /// The repository tree at `git_ref`, or `None` when it still matches
+    /// `etag`.
+    async fn tree(
+        &self,
+        client: &Client,
+        git_ref: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<TreeWithEtag>> {
+        let url = format!("{}/git/trees/{}?recursive=1", self.api_url, git_ref);
+        let response = self.get(client, &url, etag).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        Ok(Some((response.json().await?, etag)))
+    }
+
+    /// Download the node sources listed in `tree`, several at a time.
+    async fn catalogue(&self, client: &Client, git_ref: &str, tree: Tree) -> Result<Catalogue> {
+        let paths = tree.tree.into_iter().filter_map(|entry| {
+            (entry.entry_type == "blob"
+                && package_of(&entry.path).is_some()
+                && is_node_file(&entry.path))
+            .then_some(entry.path)
+        });
+        let sources: Vec<(String, String)> = stream::iter(paths)
+            .map(|path| async move {
+                let url = format!("{}/{}/{}", self.raw_url, git_ref, path);
+                let text = self
+                    .get(client, &url, None)
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await?;
+                anyhow::Ok((path, text))
+            })
+            .buffered(self.concurrency)
+            .try_collect()
+            .await?;
+
+        let mut builder = CatalogueBuilder::default();
+        for (path, text) in sources {
+            if let Some((package, rel)) = package_of(&path) {
+                builder.add(package, rel, &text);
+            }
+        }
+        Ok(builder.build())
+    }
+
+    /// Fetch the node descriptors at `git_ref`.
+    pub async fn fetch(&self, client: &Client, git_ref: &str) -> Result<Catalogue> {
+        let (tree, _) = self
+            .tree(client, git_ref, None)
+            .await?
+            .context("GitHub answered 304 Not Modified to an unconditional request")?;
+        self.catalogue(client, git_ref, tree).await
+    }
+}
+
+/// Fetch the latest node descriptors from the `master` branch of the n8n
+/// repository
+pub async fn fetch_node_versions(client: &Client) -> Result<Catalogue> {
+    GitHubRepo::from_env().fetch(client, MASTER).await
+}
+
+/// The package an installed package directory holds, from the `name` in
+/// its `package.json`. Defaults to `n8n-nodes-base`.
+fn installed_package(dir: &Path) -> NodePackage {
+    let name = fs::read(dir.join("package.json"))
+        .ok()
+        .and_then(|data| serde_json::from_slice::<Value>(&data).ok())
+        .and_then(|json| json.get("name").and_then(Value::as_str).map(str::to_string));
+    NODE_PACKAGES
+        .iter()
+        .find(|p| name.as_deref() == Some(p.name))
+        .copied()
+        .unwrap_or(NODE_PACKAGES[0])
+}
+
+/// Descriptors of the built-in and LangChain nodes found in a local n8n
+/// checkout, a `node_modules` directory or a single installed package.
+fn catalogue_from_path(root: &Path) -> Result<Catalogue> {
+    let mut dirs: Vec<(NodePackage, PathBuf)> = NODE_PACKAGES
+        .iter()
+        .flat_map(|package| {
+            [
+                root.join(package.dir),
+                root.join("node_modules").join(package.name).join("dist/nodes"),
+                root.join(package.name).join("dist/nodes"),
+            ]
+            .into_iter()
+            .find(|d| d.is_dir())
+            .map(|dir| (*package, dir))
+        })
+        .collect();
+    if dirs.is_empty() {
+        let dir = root.join("dist/nodes");
+        let dir = if dir.is_dir() { dir } else { root.to_path_buf() };
+        dirs.push((installed_package(root), dir));
+    }
+
+    let mut builder = CatalogueBuilder::default();
+    for (package, dir) in dirs {
+        let mut pending = vec![dir.clone()];
+        while let Some(current) = pending.pop() {
+            let entries = fs::read_dir(&current)
+                .with_context(|| format!("Failed to read directory {}", current.display()))?;
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if is_node_file(&path.to_string_lossy()) {
+                    let text = fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?;
+                    builder.add(package, path.strip_prefix(&dir).unwrap_or(&path), &text);
+                }
+            }
+        }
+    }
+    let catalogue = builder.build();
+    if catalogue.is_empty() {
+        anyhow::bail!("No n8n node sources found in {}", root.display());
+    }
+    Ok(catalogue)
+}
+
+/// The n8n version the instance at `host` runs, from its public settings.
+pub async fn instance_version(client: &Client, host: &Url) -> Result<String> {
+    let settings: Value = client
+        .get(host.join("rest/settings")?)
         .send()
         .await?
         .error_for_status()?
         .json()
         .await?;
+    settings
+        .pointer("/data/versionCli")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .context("The instance settings do not include its version")
+}
 
-    let file_re = Regex::new(r"^packages/nodes-base/nodes/([^/]+)/.*\.node.[jt]s$")?;
-    let version_re = Regex::new(r"version:\s*(\d+)")?;
-    let mut map: HashMap<String, u32> = HashMap::new();
-
-    for entry in tree.tree {
-        if entry.entry_type == "blob" {
-            if let Some(caps) = file_re.captures(&entry.path) {
-                let node_name = caps.get(1).unwrap().as_str().to_string();
-                let raw_url = format!(
-                    "https://raw.githubusercontent.com/n8n-io/n8n/master/{}",
-                    entry.path
-                );
-                let text = client
-                    .get(&raw_url)
-                    .header("User-Agent", "n8n-workflow-sync")
-                    .send()
-                    .await?
-                    .error_for_status()?
-                    .text()
-                    .await?;
+/// Where node versions are read from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum NodeSource {
+    /// The node types served by the connected instance at `types/nodes.json`.
+    #[default]
+    Instance,
+    /// A `nodes.json` dump of an instance's node types.
+    File(PathBuf),
+    /// The n8n repository at a git tag. `None` uses the tag matching the
+    /// version of the connected instance, e.g. `n8n@1.64.0`.
+    Tag(Option<String>),
+    /// A local n8n checkout, `n8n-nodes-base` package or `node_modules`.
+    Path(PathBuf),
+    /// The `master` branch of the n8n repository.
+    GitHub,
+}
 
-                if let Some(v_caps) = version_re.captures(&text) {
-                    if let Ok(v) = v_caps[1].parse::<u32>() {
-                        map.entry(node_name)
-                            .and_modify(|e| *e = (*e).max(v))
-                            .or_insert(v);
-                    }
+impl FromStr for NodeSource {
+    type Err = anyhow::Error;
+
+    /// Parse `instance`, `file:PATH`, `tag`, `tag:NAME`, `path:DIR` or
+    /// `github`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (s, None),
+        };
+        Ok(match (kind, arg) {
+            ("instance", None) => NodeSource::Instance,
+            ("github", None) => NodeSource::GitHub,
+            ("tag", tag) => NodeSource::Tag(tag.map(str::to_string)),
+            ("file", Some(path)) => NodeSource::File(PathBuf::from(path)),
+            ("path", Some(path)) => NodeSource::Path(PathBuf::from(path)),
+            _ => anyhow::bail!(
+                "Invalid node source \"{}\". \
+                 Use instance, file:PATH, tag, tag:NAME, path:DIR or github",
+                s
+            ),
+        })
+    }
+}
+
+impl fmt::Display for NodeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeSource::Instance => write!(f, "instance"),
+            NodeSource::File(path) => write!(f, "file:{}", path.display()),
+            NodeSource::Tag(None) => write!(f, "tag"),
+            NodeSource::Tag(Some(tag)) => write!(f, "tag:{}", tag),
+            NodeSource::Path(path) => write!(f, "path:{}", path.display()),
+            NodeSource::GitHub => write!(f, "github"),
+        }
+    }
+}
+
+impl Serialize for NodeSource {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Node versions as stored in the cache.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedVersions {
+    /// When GitHub last confirmed these versions, in seconds since the epoch.
+    pub fetched_at: u64,
+    /// ETag of the repository tree they were built from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// The packages whose nodes were read, e.g. `n8n-nodes-base`.
+    #[serde(default)]
+    pub packages: Vec<String>,
+    pub nodes: Catalogue,
+}
+
+impl CachedVersions {
+    /// Versions just read from every package in the n8n repository.
+    pub fn new(nodes: Catalogue, etag: Option<String>) -> Self {
+        Self {
+            fetched_at: now(),
+            etag,
+            packages: NODE_PACKAGES.iter().map(|p| p.name.to_string()).collect(),
+            nodes,
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.fetched_at))
+    }
+
+    /// Whether every package read today was read into this copy. Copies
+    /// cached by older releases lack the LangChain nodes and are refetched.
+    pub fn is_complete(&self) -> bool {
+        NODE_PACKAGES
+            .iter()
+            .all(|p| self.packages.iter().any(|name| name == p.name))
+    }
+}
+
+/// How current a set of node versions is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheStatus {
+    /// The cache was recent enough to use without asking GitHub.
+    Fresh,
+    /// GitHub confirmed the cached versions are still current.
+    Revalidated,
+    /// The versions were just read from their source.
+    Fetched,
+    /// GitHub could not be reached and outdated cached versions were used.
+    Stale,
+}
+
+impl CacheStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheStatus::Fresh => "fresh",
+            CacheStatus::Revalidated => "revalidated",
+            CacheStatus::Fetched => "fetched",
+            CacheStatus::Stale => "stale",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeVersions {
+    pub nodes: Catalogue,
+    /// The source the versions were read from, which is GitHub `master`
+    /// when the configured source failed.
+    pub source: NodeSource,
+    pub status: CacheStatus,
+    /// When the versions were last confirmed, in seconds since the epoch.
+    pub fetched_at: u64,
+}
+
+/// On-disk cache of node versions read from GitHub, one file per git ref,
+/// in the user cache directory.
+#[derive(Debug, Clone)]
+pub struct NodeCache {
+    dir: PathBuf,
+    ttl: Duration,
+    github: GitHubRepo,
+}
+
+impl NodeCache {
+    /// A cache stored in `dir`, filled from the n8n repository without
+    /// authentication.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: CACHE_TTL,
+            github: GitHubRepo::default(),
+        }
+    }
+
+    /// The cache in the platform cache directory (for example
+    /// `~/.cache/n8n-workflow-sync` on Linux), or in `N8N_SYNC_CACHE_DIR`,
+    /// filled using `GITHUB_TOKEN` when it is set.
+    pub fn open() -> Result<Self> {
+        let dir = match std::env::var_os(CACHE_DIR_ENV) {
+            Some(dir) => PathBuf::from(dir),
+            None => directories::ProjectDirs::from("", "", APP_NAME)
+                .context("Unable to determine the user cache directory")?
+                .cache_dir()
+                .to_path_buf(),
+        };
+        Ok(Self::new(dir).with_github(GitHubRepo::from_env()))
+    }
+
+    pub fn with_github(mut self, github: GitHubRepo) -> Self {
+        self.github = github;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The cache file for versions read at `git_ref`.
+    pub fn path(&self, git_ref: &str) -> PathBuf {
+        let name: String = git_ref
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '-'
                 }
+            })
+            .collect();
+        self.dir.join(format!("node-versions-{}.json", name))
+    }
+
+    /// The cache file holding versions read from `source`, if it is cached.
+    pub fn file_for(&self, source: &NodeSource) -> Option<PathBuf> {
+        match source {
+            NodeSource::GitHub => Some(self.path(MASTER)),
+            NodeSource::Tag(Some(tag)) => Some(self.path(tag)),
+            _ => None,
+        }
+    }
+
+    /// The versions cached for `git_ref`, `None` if nothing was cached yet.
+    pub fn load(&self, git_ref: &str) -> Result<Option<CachedVersions>> {
+        let path = self.path(git_ref);
+        match fs::read(&path) {
+            Ok(data) => {
+                Ok(Some(serde_json::from_slice(&data).with_context(|| {
+                    format!("Failed to parse {}", path.display())
+                })?))
             }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
         }
     }
 
-    Ok(map)
+    pub fn store(&self, git_ref: &str, cached: &CachedVersions) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create directory {}", self.dir.display()))?;
+        let path = self.path(git_ref);
+        fs::write(&path, serde_json::to_vec_pretty(cached)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Node versions read from the n8n repository at `git_ref`, through the
+    /// cache. GitHub is asked first when the cache is older than its TTL or
+    /// `refresh` is set, revalidating a cached copy with its ETag. If GitHub
+    /// cannot be reached an outdated copy is used with a warning, unless
+    /// `refresh` asked for current versions.
+    pub async fn github(
+        &self,
+        client: &Client,
+        git_ref: &str,
+        refresh: bool,
+    ) -> Result<NodeVersions> {
+        let (source, ttl) = if git_ref == MASTER {
+            (NodeSource::GitHub, self.ttl)
+        } else {
+            (NodeSource::Tag(Some(git_ref.to_string())), Duration::MAX)
+        };
+        let cached = self
+            .load(git_ref)
+            .unwrap_or_else(|e| {
+                tracing::warn!("ignoring unreadable node version cache: {:#}", e);
+                None
+            })
+            .filter(CachedVersions::is_complete);
+        if let Some(cached) = &cached
+            && !refresh
+            && cached.age() < ttl
+        {
+            return Ok(NodeVersions {
+                nodes: cached.nodes.clone(),
+                source,
+                status: CacheStatus::Fresh,
+                fetched_at: cached.fetched_at,
+            });
+        }
+
+        let etag = cached.as_ref().and_then(|c| c.etag.as_deref());
+        let fetched = match self.github.tree(client, git_ref, etag).await {
+            Ok(Some((tree, etag))) => {
+                self.github
+                    .catalogue(client, git_ref, tree)
+                    .await
+                    .map(|nodes| (CachedVersions::new(nodes, etag), CacheStatus::Fetched))
+            }
+            Ok(None) => cached
+                .clone()
+                .context("GitHub answered 304 Not Modified without a cached copy")
+                .map(|mut cached| {
+                    cached.fetched_at = now();
+                    (cached, CacheStatus::Revalidated)
+                }),
+            Err(e) => Err(e),
+        };
+
+        match (fetched, cached) {
+            (Ok((fresh, status)), _) => {
+                self.store(git_ref, &fresh)?;
+                Ok(NodeVersions {
+                    nodes: fresh.nodes,
+                    source,
+                    status,
+                    fetched_at: fresh.fetched_at,
+                })
+            }
+            (Err(e), Some(cached)) if !refresh => {
+                tracing::warn!(
+                    age_hours = cached.age().as_secs() / 3600,
+                    "could not refresh node versions from GitHub, using the cached copy: {:#}",
+                    e
+                );
+                Ok(NodeVersions {
+                    nodes: cached.nodes,
+                    source,
+                    status: CacheStatus::Stale,
+                    fetched_at: cached.fetched_at,
+                })
+            }
+            (Err(e), _) => Err(e.context("Failed to fetch node versions from GitHub")),
+        }
+    }
 }
 
-/// Fetch node versions and save them as `node-versions.json` in the given directory
-pub async fn save_node_versions<P: AsRef<Path>>(dir: P) -> Result<()> {
-    let versions = fetch_node_versions().await?;
-    let path = dir.as_ref().join("node-versions.json");
-    let data = serde_json::to_vec_pretty(&versions)?;
-    fs::write(path, data)?;
-    Ok(())
+async fn fetch_from_source(
+    client: &Client,
+    host: &Url,
+    source: &NodeSource,
+    cache: &NodeCache,
+    refresh: bool,
+) -> Result<NodeVersions> {
+    let nodes = match source {
+        NodeSource::GitHub => return cache.github(client, MASTER, refresh).await,
+        NodeSource::Tag(Some(tag)) => return cache.github(client, tag, refresh).await,
+        NodeSource::Tag(None) => {
+            let tag = format!("n8n@{}", instance_version(client, host).await?);
+            return cache.github(client, &tag, refresh).await;
+        }
+        NodeSource::Instance => {
+            let types: Value = client
+                .get(host.join("types/nodes.json")?)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            catalogue_from_descriptions(&types)?
+        }
+        NodeSource::File(path) => {
+            let data =
+                fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let types: Value = serde_json::from_slice(&data)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            catalogue_from_descriptions(&types)?
+        }
+        NodeSource::Path(path) => catalogue_from_path(path)?,
+    };
+    Ok(NodeVersions {
+        nodes,
+        source: source.clone(),
+        status: CacheStatus::Fetched,
+        fetched_at: now(),
+    })
+}
+
+/// Read node versions from `source`, falling back to GitHub `master` with a
+/// warning when it fails. `host` is the instance the `instance` and `tag`
+/// sources refer to. See [`NodeCache::github`] for `refresh`.
+pub async fn node_versions(
+    client: &Client,
+    host: &Url,
+    source: &NodeSource,
+    cache: &NodeCache,
+    refresh: bool,
+) -> Result<NodeVersions> {
+    match fetch_from_source(client, host, source, cache, refresh).await {
+        Ok(versions) => Ok(versions),
+        Err(e) if *source != NodeSource::GitHub => {
+            tracing::warn!(
+                "could not read node versions from {}, falling back to GitHub master: {:#}",
+                source,
+                e
+            );
+            cache.github(client, MASTER, refresh).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Save the descriptors of the nodes from `source` as `node-versions.json`
+/// in the given directory, keyed by node type and using the cache where
+/// possible.
+pub async fn save_node_versions<P: AsRef<Path>>(
+    client: &N8nClient,
+    source: &NodeSource,
+    dir: P,
+) -> Result<NodeVersions> {
+    let versions = node_versions(
+        client.http(),
+        &client.config().host,
+        source,
+        &NodeCache::open()?,
+        false,
+    )
+    .await?;
+    let path = dir.as_ref().join(NODE_VERSIONS_FILE);
+    let mut data = serde_json::to_vec_pretty(&versions.nodes)?;
+    data.push(b'\n');
+    fs::write(&path, data).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(versions)
+}
+
+/// Read a `node-versions.json` written by [`save_node_versions`].
+pub fn load_node_versions(path: &Path) -> Result<Catalogue> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_slice(&data).with_context(|| {
+        format!(
+            "Failed to parse {}. Pull the workflow again to update it",
+            path.display()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// A client that cannot connect anywhere, proving nothing is requested.
+    fn offline() -> Client {
+        Client::builder()
+            .proxy(reqwest::Proxy::all("http://127.0.0.1:9").unwrap())
+            .build()
+            .unwrap()
+    }
+
+    fn catalogue(nodes: &[(&str, &[f64])]) -> Catalogue {
+        nodes
+            .iter()
+            .map(|(node_type, versions)| {
+                let node = NodeDescriptor {
+                    node_type: node_type.to_string(),
+                    versions: versions.iter().copied().map(Version).collect(),
+                    ..NodeDescriptor::default()
+                };
+                (node_type.to_string(), node)
+            })
+            .collect()
+    }
+
+    fn cached(age: u64) -> CachedVersions {
+        CachedVersions {
+            fetched_at: now() - age,
+            ..CachedVersions::new(
+                catalogue(&[("n8n-nodes-base.httpRequest", &[4.2])]),
+                Some("\"abc\"".into()),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn fresh_cache_is_used_without_network() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = NodeCache::new(dir.path().join("cache"));
+        assert_eq!(cache.load(MASTER).unwrap(), None);
+
+        let cached = cached(60);
+        cache.store(MASTER, &cached).unwrap();
+        assert_eq!(cache.load(MASTER).unwrap(), Some(cached.clone()));
+
+        let fresh = cache.github(&offline(), MASTER, false).await.unwrap();
+        assert_eq!(fresh.status, CacheStatus::Fresh);
+        assert_eq!(fresh.nodes, cached.nodes);
+
+        // Past its TTL the cache is still used when GitHub is unreachable.
+        let expired = cache.clone().with_ttl(Duration::from_secs(1));
+        let stale = expired.github(&offline(), MASTER, false).await.unwrap();
+        assert_eq!(stale.status, CacheStatus::Stale);
+        assert_eq!(stale.fetched_at, cached.fetched_at);
+
+        assert!(expired.github(&offline(), MASTER, true).await.is_err());
+        let empty = NodeCache::new(dir.path().join("empty"));
+        assert!(empty.github(&offline(), MASTER, false).await.is_err());
+    }
+
+    const HTTP_REQUEST: &str = r#"
+        import { VersionedNodeType } from 'n8n-workflow';
+
+        export class HttpRequest extends VersionedNodeType {
+            constructor() {
+                const baseDescription: INodeTypeBaseDescription = {
+                    displayName: 'HTTP Request',
+                    name: 'httpRequest',
+                    icon: 'fa:at',
+                    group: ['output'],
+                    description: 'Makes an HTTP request and returns the response data',
+                    defaultVersion: 4.2,
+                };
+                const nodeVersions: IVersionedNodeType['nodeVersions'] = {
+                    1: new HttpRequestV1(baseDescription),
+                    2: new HttpRequestV2(baseDescription),
+                };
+                super(nodeVersions, baseDescription);
+            }
+        }
+    "#;
+
+    const HTTP_REQUEST_V3: &str = r#"
+        export class HttpRequestV3 implements INodeType {
+            description: INodeTypeDescription;
+
+            constructor(baseDescription: INodeTypeBaseDescription) {
+                this.description = {
+                    ...baseDescription,
+                    subtitle: '={{$parameter["method"] + ": " + $parameter["url"]}}',
+                    version: [3, 4, 4.1, 4.2],
+                    defaults: { name: 'HTTP Request', color: '#0004F5' },
+                    // version: 99 in a comment is ignored
+                    credentials: [
+                        {
+                            name: 'httpSslAuth',
+                            required: true,
+                            displayOptions: { show: { provideSslCertificates: [true] } },
+                        },
+                    ],
+                    properties: [
+                        { displayName: 'Method', name: 'method', type: 'options', default: 'GET' },
+                        { displayName: 'Version', name: 'version', default: 7 },
+                    ],
+                };
+            }
+        }
+    "#;
+
+    #[test]
+    fn parses_versioned_node_sources() {
+        let base = NODE_PACKAGES[0];
+        let mut builder = CatalogueBuilder::default();
+        builder.add(base, Path::new("HttpRequest/HttpRequest.node.ts"), HTTP_REQUEST);
+        builder.add(
+            base,
+            Path::new("HttpRequest/V3/HttpRequestV3.node.ts"),
+            HTTP_REQUEST_V3,
+        );
+        builder.add(
+            base,
+            Path::new("HttpRequest/V1/HttpRequestV1.node.ts"),
+            "this.description = { ...baseDescription, version: 1, credentials: [] }",
+        );
+        builder.add(
+            base,
+            Path::new("Function/Function.node.ts"),
+            "description: INodeTypeDescription = { displayName: 'Function', \
+             name: 'function', hidden: true, version: 1 }",
+        );
+        let catalogue = builder.build();
+
+        assert_eq!(
+            catalogue["n8n-nodes-base.httpRequest"],
+            NodeDescriptor {
+                node_type: "n8n-nodes-base.httpRequest".into(),
+                display_name: Some("HTTP Request".into()),
+                versions: [1.0, 3.0, 4.0, 4.1, 4.2].map(Version).to_vec(),
+                default_version: Some(Version(4.2)),
+                credentials: vec!["httpSslAuth".into()],
+                deprecated: false,
+            }
+        );
+        assert!(catalogue["n8n-nodes-base.function"].deprecated);
+        assert_eq!(catalogue.len(), 2);
+
+        let json = serde_json::to_value(&catalogue["n8n-nodes-base.httpRequest"]).unwrap();
+        assert_eq!(json["versions"], json!([1, 3, 4, 4.1, 4.2]));
+        assert_eq!(json["defaultVersion"], json!(4.2));
+    }
+
+    #[test]
+    fn parses_node_sources() {
+        for s in [
+            "instance",
+            "github",
+            "tag",
+            "tag:n8n@1.64.0",
+            "file:nodes.json",
+            "path:../n8n",
+        ] {
+            assert_eq!(s.parse::<NodeSource>().unwrap().to_string(), s);
+        }
+        assert!("svn:trunk".parse::<NodeSource>().is_err());
+        assert!("file".parse::<NodeSource>().is_err());
+    }
+
+    #[tokio::test]
+    async fn reads_the_node_types_of_the_instance() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/types/nodes.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {
+                    "name": "n8n-nodes-base.httpRequest",
+                    "displayName": "HTTP Request",
+                    "version": [3, 4, 4.1, 4.2],
+                    "defaultVersion": 4.2,
+                    "credentials": [{"name": "httpSslAuth", "required": true}]
+                },
+                {"name": "n8n-nodes-base.httpRequest", "version": 1, "defaultVersion": 4.2},
+                {"name": "n8n-nodes-base.webhook", "version": 2},
+                {"name": "n8n-nodes-base.function", "version": 1, "hidden": true}
+            ])))
+            .mount(&server)
+            .await;
+        let host = Url::parse(&server.uri()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let cache = NodeCache::new(dir.path());
+
+        let versions = node_versions(&Client::new(), &host, &NodeSource::Instance, &cache, false)
+            .await
+            .unwrap();
+        assert_eq!(versions.source, NodeSource::Instance);
+        let http = &versions.nodes["n8n-nodes-base.httpRequest"];
+        assert_eq!(http.versions, [1.0, 3.0, 4.0, 4.1, 4.2].map(Version));
+        assert_eq!(http.latest(), Some(Version(4.2)));
+        assert_eq!(http.credentials, ["httpSslAuth"]);
+        assert_eq!(
+            versions.nodes["n8n-nodes-base.webhook"].latest(),
+            Some(Version(2.0))
+        );
+        assert!(versions.nodes["n8n-nodes-base.function"].deprecated);
+    }
+
+    #[tokio::test]
+    async fn reads_local_sources_and_falls_back_to_github() {
+        let dir = tempfile::tempdir().unwrap();
+        let nodes = dir
+            .path()
+            .join("node_modules/n8n-nodes-base/dist/nodes/Webhook");
+        fs::create_dir_all(&nodes).unwrap();
+        fs::write(
+            nodes.join("Webhook.node.js"),
+            "this.description = { displayName: 'Webhook', name: 'webhook', version: [1, 1.1, 2] }",
+        )
+        .unwrap();
+        let agent = dir
+            .path()
+            .join("node_modules/@n8n/n8n-nodes-langchain/dist/nodes/agents/Agent");
+        fs::create_dir_all(&agent).unwrap();
+        fs::write(
+            agent.join("Agent.node.js"),
+            "this.description = { displayName: 'AI Agent', name: 'agent', version: [1, 2] }",
+        )
+        .unwrap();
+        let host = Url::parse("http://127.0.0.1:9/").unwrap();
+        let cache = NodeCache::new(dir.path().join("cache"));
+        cache.store(MASTER, &cached(60)).unwrap();
+
+        let source = NodeSource::Path(dir.path().to_path_buf());
+        let local = node_versions(&offline(), &host, &source, &cache, false)
+            .await
+            .unwrap();
+        assert_eq!(local.source, source);
+        assert_eq!(
+            local.nodes["n8n-nodes-base.webhook"].latest(),
+            Some(Version(2.0))
+        );
+        assert_eq!(
+            local.nodes["@n8n/n8n-nodes-langchain.agent"].latest(),
+            Some(Version(2.0))
+        );
+
+        // The instance cannot be reached, so the cached master copy is used.
+        let fallback = node_versions(&offline(), &host, &NodeSource::Instance, &cache, false)
+            .await
+            .unwrap();
+        assert_eq!(fallback.source, NodeSource::GitHub);
+        assert_eq!(fallback.nodes, cached(0).nodes);
+    }
+
+    /// Serve a tree of two node sources at `master`, with the ETag `"v1"`.
+    async fn github_stand_in() -> (MockServer, GitHubRepo) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/n8n-io/n8n/git/trees/master"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/n8n-io/n8n/git/trees/master"))
+            .and(header("Authorization", "Bearer secret"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_json(json!({"tree": [
+                        {"path": "packages/nodes-base/nodes/Webhook", "type": "tree"},
+                        {
+                            "path": "packages/nodes-base/nodes/Webhook/Webhook.node.ts",
+                            "type": "blob"
+                        },
+                        {"path": "packages/nodes-base/nodes/Set/Set.node.ts", "type": "blob"},
+                        {"path": "packages/nodes-base/nodes/Set/v2/SetV2.node.ts", "type": "blob"},
+                        {
+                            "path": "packages/@n8n/nodes-langchain/nodes/agents/Agent/Agent.node.ts",
+                            "type": "blob"
+                        },
+                        {"path": "packages/cli/src/index.ts", "type": "blob"}
+                    ]})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        for (file, source) in [
+            (
+                "nodes-base/nodes/Webhook/Webhook.node.ts",
+                "description = { displayName: 'Webhook', name: 'webhook', version: [1, 1.1, 2] }",
+            ),
+            (
+                "nodes-base/nodes/Set/Set.node.ts",
+                "baseDescription = { displayName: 'Edit Fields (Set)', name: 'set', \
+                 defaultVersion: 3.4 }",
+            ),
+            (
+                "nodes-base/nodes/Set/v2/SetV2.node.ts",
+                "this.description = { ...baseDescription, version: [2, 3, 3.1, 3.2, 3.3, 3.4] }",
+            ),
+            (
+                "@n8n/nodes-langchain/nodes/agents/Agent/Agent.node.ts",
+                "description: INodeTypeDescription = { displayName: 'AI Agent', name: 'agent', \
+                 version: [1, 1.1, 2] }",
+            ),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!("/raw/master/packages/{}", file)))
+                .and(header("Authorization", "Bearer secret"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(source))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let repo = GitHubRepo::default()
+            .with_base_urls(
+                format!("{}/repos/n8n-io/n8n", server.uri()),
+                format!("{}/raw", server.uri()),
+            )
+            .with_token(Some("secret".into()))
+            .with_concurrency(2);
+        (server, repo)
+    }
+
+    #[tokio::test]
+    async fn downloads_and_revalidates_node_sources_from_github() {
+        let (_server, repo) = github_stand_in().await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache = NodeCache::new(dir.path()).with_github(repo);
+
+        let fetched = cache.github(&Client::new(), MASTER, false).await.unwrap();
+        assert_eq!(fetched.status, CacheStatus::Fetched);
+        assert_eq!(fetched.source, NodeSource::GitHub);
+        assert_eq!(
+            fetched.nodes.keys().collect::<Vec<_>>(),
+            [
+                "@n8n/n8n-nodes-langchain.agent",
+                "n8n-nodes-base.set",
+                "n8n-nodes-base.webhook"
+            ]
+        );
+        let set = &fetched.nodes["n8n-nodes-base.set"];
+        assert_eq!(set.latest(), Some(Version(3.4)));
+        assert_eq!(set.versions.len(), 6);
+        assert_eq!(
+            cache.load(MASTER).unwrap().unwrap().etag.as_deref(),
+            Some("\"v1\"")
+        );
+
+        // Refreshing sends the ETag and keeps the cached copy on 304.
+        let revalidated = cache.github(&Client::new(), MASTER, true).await.unwrap();
+        assert_eq!(revalidated.status, CacheStatus::Revalidated);
+        assert_eq!(revalidated.nodes, fetched.nodes);
+    }
+
+    #[tokio::test]
+    async fn caches_without_the_langchain_nodes_are_refetched() {
+        let (_server, repo) = github_stand_in().await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache = NodeCache::new(dir.path()).with_github(repo);
+        let mut old = cached(60);
+        old.packages = vec![NODES_BASE_PACKAGE.into()];
+        cache.store(MASTER, &old).unwrap();
+
+        let fetched = cache.github(&Client::new(), MASTER, false).await.unwrap();
+        assert_eq!(fetched.status, CacheStatus::Fetched);
+        assert!(fetched.nodes.contains_key("@n8n/n8n-nodes-langchain.agent"));
+    }
+
+    #[tokio::test]
+    async fn reports_when_the_rate_limit_resets() {
+        let server = MockServer::start().await;
+        let reset_at = now() + 30 * 60;
+        Mock::given(method("GET"))
+            .and(path("/git/trees/master"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("X-RateLimit-Remaining", "0")
+                    .insert_header("X-RateLimit-Reset", reset_at.to_string().as_str())
+                    .set_body_json(json!({"message": "API rate limit exceeded"})),
+            )
+            .mount(&server)
+            .await;
+        let repo = GitHubRepo::default().with_base_urls(server.uri(), server.uri());
+
+        let err = repo.fetch(&Client::new(), MASTER).await.unwrap_err();
+        let limit = err.downcast_ref::<RateLimitExceeded>().unwrap();
+        assert_eq!(limit.reset_at, Some(reset_at));
+        let message = err.to_string();
+        assert!(message.contains("resets in 30 minutes"), "{}", message);
+        assert!(message.contains("Set GITHUB_TOKEN"), "{}", message);
+    }
 }
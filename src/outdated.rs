@@ -0,0 +1,207 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::nodes::{Catalogue, NODE_VERSIONS_FILE, Version};
+use crate::status::find_workflow_files;
+
+/// Why a node is reported by `outdated`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NodeState {
+    /// A newer version of the node exists.
+    Outdated,
+    /// The node is hidden from the editor or marked deprecated.
+    Deprecated,
+    /// The node type is not in the catalogue.
+    Unknown,
+}
+
+impl NodeState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NodeState::Outdated => "outdated",
+            NodeState::Deprecated => "deprecated",
+            NodeState::Unknown => "unknown",
+        }
+    }
+
+    /// Whether `outdated --check` fails on a node in this state. Unknown
+    /// nodes, such as community nodes, are reported but cannot be fixed by
+    /// updating the workflow.
+    pub fn fails_check(self) -> bool {
+        self != NodeState::Unknown
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutdatedNode {
+    /// The workflow file the node is in.
+    pub path: PathBuf,
+    /// The node's name in the workflow.
+    pub name: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub version: Version,
+    /// The version new nodes of this type get, `None` for unknown types.
+    pub latest: Option<Version>,
+    /// How many supported versions are newer than the node's.
+    pub behind: usize,
+    pub state: NodeState,
+}
+
+/// The workflow files `outdated` checks for `path`: the file itself, or the
+/// workflows found below a directory.
+pub fn workflow_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    find_workflow_files(path, &mut files)?;
+    files.sort();
+    files.dedup();
+    files.retain(|p| p.exists());
+    Ok(files)
+}
+
+/// The `node-versions.json` written when the workflow at `path` was pulled:
+/// the one in its directory, or in the nearest parent directory for
+/// workflows mirrored with `pull --all`.
+pub fn find_node_versions(path: &Path) -> Option<PathBuf> {
+    let dir = std::path::absolute(path).ok()?.parent()?.to_path_buf();
+    dir.ancestors()
+        .map(|d| d.join(NODE_VERSIONS_FILE))
+        .find(|f| f.is_file())
+}
+
+/// The nodes of `workflow` that are outdated, deprecated or unknown to
+/// `catalogue`, ordered by node name. Nodes without a `typeVersion` are
+/// taken to be version 1, as in n8n.
+pub fn check(path: &Path, workflow: &Value, catalogue: &Catalogue) -> Vec<OutdatedNode> {
+    let mut found = Vec::new();
+    for node in workflow
+        .get("nodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let Some(node_type) = node.get("type").and_then(Value::as_str) else {
+            continue;
+        };
+        let version = Version(
+            node.get("typeVersion")
+                .and_then(Value::as_f64)
+                .unwrap_or(1.0),
+        );
+        let descriptor = catalogue.get(node_type);
+        let latest = descriptor.and_then(|d| d.latest());
+        let behind = match (descriptor, latest) {
+            (Some(d), Some(latest)) => d
+                .versions
+                .iter()
+                .filter(|v| **v > version && **v <= latest)
+                .count(),
+            _ => 0,
+        };
+        let state = match descriptor {
+            None => NodeState::Unknown,
+            Some(d) if d.deprecated => NodeState::Deprecated,
+            Some(_) if latest.is_some_and(|l| version < l) => NodeState::Outdated,
+            Some(_) => continue,
+        };
+        found.push(OutdatedNode {
+            path: path.to_path_buf(),
+            name: node
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            node_type: node_type.to_string(),
+            version,
+            latest,
+            behind,
+            state,
+        });
+    }
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::NodeDescriptor;
+    use serde_json::json;
+
+    fn descriptor(node_type: &str, versions: &[f64], deprecated: bool) -> (String, NodeDescriptor) {
+        let node = NodeDescriptor {
+            node_type: node_type.to_string(),
+            versions: versions.iter().copied().map(Version).collect(),
+            deprecated,
+            ..NodeDescriptor::default()
+        };
+        (node_type.to_string(), node)
+    }
+
+    #[test]
+    fn reports_outdated_deprecated_and_unknown_nodes() {
+        let catalogue = Catalogue::from([
+            descriptor(
+                "n8n-nodes-base.httpRequest",
+                &[1.0, 2.0, 3.0, 4.0, 4.1, 4.2],
+                false,
+            ),
+            descriptor("n8n-nodes-base.webhook", &[1.0, 1.1, 2.0], false),
+            descriptor("n8n-nodes-base.function", &[1.0], true),
+        ]);
+        let workflow = json!({
+            "nodes": [
+                {"name": "Webhook", "type": "n8n-nodes-base.webhook", "typeVersion": 2},
+                {"name": "Fetch", "type": "n8n-nodes-base.httpRequest", "typeVersion": 3},
+                {"name": "Legacy", "type": "n8n-nodes-base.function"},
+                {"name": "Custom", "type": "n8n-nodes-acme.widget", "typeVersion": 1.5}
+            ]
+        });
+
+        let found = check(Path::new("workflow.json"), &workflow, &catalogue);
+        let summary: Vec<_> = found
+            .iter()
+            .map(|n| (n.name.as_str(), n.state, n.version, n.latest, n.behind))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Custom", NodeState::Unknown, Version(1.5), None, 0),
+                (
+                    "Fetch",
+                    NodeState::Outdated,
+                    Version(3.0),
+                    Some(Version(4.2)),
+                    3
+                ),
+                (
+                    "Legacy",
+                    NodeState::Deprecated,
+                    Version(1.0),
+                    Some(Version(1.0)),
+                    0
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_node_versions_in_parent_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let workflow = dir.path().join("billing").join("workflow.json");
+        std::fs::create_dir_all(workflow.parent().unwrap()).unwrap();
+        assert_eq!(find_node_versions(&workflow), None);
+
+        std::fs::write(dir.path().join(NODE_VERSIONS_FILE), "{}").unwrap();
+        assert_eq!(
+            find_node_versions(&workflow),
+            Some(dir.path().join(NODE_VERSIONS_FILE))
+        );
+    }
+}
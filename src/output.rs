@@ -0,0 +1,805 @@
+use clap::ValueEnum;
+use n8n_workflow_sync::api::Workflow;
+use n8n_workflow_sync::diff::{Change, WorkflowDiff};
+use n8n_workflow_sync::merge::Conflict;
+use n8n_workflow_sync::mirror::{FileStatus, MirroredWorkflow};
+use n8n_workflow_sync::nodes::CacheStatus;
+use n8n_workflow_sync::outdated::{NodeState, OutdatedNode};
+use n8n_workflow_sync::status::{SyncState, WorkflowStatus};
+use n8n_workflow_sync::sync::SyncOutcome;
+use serde::Serialize;
+use std::fmt::Display;
+use std::path::Path;
+
+/// How command results are written to stdout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-friendly messages
+    #[default]
+    Text,
+    /// Column-aligned table
+    Table,
+    /// Structured JSON, suitable for `jq`
+    Json,
+    /// Structured YAML
+    Yaml,
+}
+
+/// Writes progress messages and command reports in the selected format.
+#[derive(Clone, Copy, Debug)]
+pub struct Output {
+    format: OutputFormat,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    /// Print a progress message. For machine-readable formats this goes to
+    /// stderr so stdout only carries the final report.
+    pub fn info(&self, msg: impl Display) {
+        match self.format {
+            OutputFormat::Text => println!("{}", msg),
+            _ => eprintln!("{}", msg),
+        }
+    }
+
+    /// Print the final result of a command.
+    pub fn emit<R: Report>(&self, report: &R) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Text => {
+                for line in report.text() {
+                    println!("{}", line);
+                }
+            }
+            OutputFormat::Table => {
+                let (header, rows) = report.table();
+                print!("{}", render_table(&header, &rows));
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(report)?);
+            }
+            OutputFormat::Yaml => {
+                print!("{}", serde_yaml::to_string(report)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A command result that can be rendered in every [`OutputFormat`].
+pub trait Report: Serialize {
+    /// Lines printed in text mode.
+    fn text(&self) -> Vec<String>;
+    /// Header and rows printed in table mode.
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>);
+}
+
+/// Render rows as left-aligned columns separated by two spaces.
+pub fn render_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let mut push_row = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{:<width$}", c, width = widths[i]))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    };
+    push_row(header.to_vec());
+    for row in rows {
+        push_row(row.iter().map(String::as_str).collect());
+    }
+    out
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowSummary {
+    pub id: String,
+    pub name: String,
+    pub active: bool,
+    pub tags: Vec<String>,
+    pub updated_at: Option<String>,
+}
+
+impl From<Workflow> for WorkflowSummary {
+    fn from(wf: Workflow) -> Self {
+        Self {
+            id: wf.id,
+            name: wf.name,
+            active: wf.active,
+            tags: wf.tags.into_iter().map(|t| t.name).collect(),
+            updated_at: wf.updated_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListReport {
+    pub total: usize,
+    pub workflows: Vec<WorkflowSummary>,
+}
+
+impl Report for ListReport {
+    fn text(&self) -> Vec<String> {
+        if self.workflows.is_empty() {
+            return vec!["No workflows found on the server.".into()];
+        }
+        let mut lines = vec![format!("Found {} workflows:", self.total)];
+        lines.extend(
+            self.workflows
+                .iter()
+                .map(|wf| format!("  {}: {}", wf.id, wf.name)),
+        );
+        lines
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let rows = self
+            .workflows
+            .iter()
+            .map(|wf| {
+                vec![
+                    wf.id.clone(),
+                    wf.name.clone(),
+                    if wf.active { "yes" } else { "no" }.to_string(),
+                    wf.tags.join(","),
+                    wf.updated_at.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        (vec!["ID", "NAME", "ACTIVE", "TAGS", "UPDATED"], rows)
+    }
+}
+
+#[derive(Serialize)]
+pub struct NewReport {
+    pub id: String,
+    pub name: String,
+    pub directory: String,
+    pub path: String,
+}
+
+impl NewReport {
+    pub fn new(id: &str, name: &str, dir: &Path, path: &Path) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            directory: dir.display().to_string(),
+            path: path.display().to_string(),
+        }
+    }
+}
+
+impl Report for NewReport {
+    fn text(&self) -> Vec<String> {
+        vec![
+            format!(
+                "✓ Created workflow {} in directory: {}",
+                self.id, self.directory
+            ),
+            "✓ Initialized git repository with initial commit".into(),
+        ]
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        (
+            vec!["ID", "NAME", "PATH"],
+            vec![vec![self.id.clone(), self.name.clone(), self.path.clone()]],
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct PullReport {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    /// The commit recording this pull, `None` if nothing changed.
+    pub commit: Option<String>,
+}
+
+impl Report for PullReport {
+    fn text(&self) -> Vec<String> {
+        match &self.commit {
+            Some(oid) => vec![
+                format!("✓ Downloaded workflow {} to {}", self.id, self.path),
+                format!("✓ Committed {}", &oid[..oid.len().min(7)]),
+            ],
+            None => vec![format!(
+                "✓ {} is already up to date with workflow {}",
+                self.path, self.id
+            )],
+        }
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        (
+            vec!["ID", "NAME", "PATH"],
+            vec![vec![self.id.clone(), self.name.clone(), self.path.clone()]],
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct PullAllReport {
+    pub directory: String,
+    pub added: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub commit: Option<String>,
+    pub workflows: Vec<MirroredWorkflow>,
+}
+
+impl Report for PullAllReport {
+    fn text(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .workflows
+            .iter()
+            .filter(|wf| wf.status != FileStatus::Unchanged)
+            .map(|wf| {
+                let mark = match wf.status {
+                    FileStatus::Added => "+",
+                    FileStatus::Removed => "-",
+                    _ => "~",
+                };
+                format!("  {} {} ({})", mark, wf.path.display(), wf.id)
+            })
+            .collect();
+        lines.push(format!(
+            "✓ Pulled {} workflows into {}: {} added, {} changed, {} unchanged",
+            self.workflows.len() - self.removed,
+            self.directory,
+            self.added,
+            self.changed,
+            self.unchanged
+        ));
+        if self.removed > 0 {
+            lines.push(format!(
+                "✓ Removed {} workflows deleted from the server",
+                self.removed
+            ));
+        }
+        lines.push(match &self.commit {
+            Some(oid) => format!("✓ Committed {}", &oid[..oid.len().min(7)]),
+            None => "Already up to date, nothing to commit".into(),
+        });
+        lines
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let rows = self
+            .workflows
+            .iter()
+            .map(|wf| {
+                let status = match wf.status {
+                    FileStatus::Added => "added",
+                    FileStatus::Changed => "changed",
+                    FileStatus::Unchanged => "unchanged",
+                    FileStatus::Removed => "removed",
+                };
+                vec![
+                    wf.id.clone(),
+                    wf.name.clone(),
+                    status.to_string(),
+                    wf.path.display().to_string(),
+                ]
+            })
+            .collect();
+        (vec!["ID", "NAME", "STATUS", "PATH"], rows)
+    }
+}
+
+#[derive(Serialize)]
+pub struct OutdatedReport {
+    /// Number of nodes checked.
+    pub checked: usize,
+    pub nodes: Vec<OutdatedNode>,
+}
+
+impl OutdatedReport {
+    fn versions(node: &OutdatedNode) -> String {
+        match node.latest {
+            Some(latest) if node.state == NodeState::Outdated => format!(
+                "{} → {} ({} {} behind)",
+                node.version,
+                latest,
+                node.behind,
+                if node.behind == 1 { "version" } else { "versions" }
+            ),
+            _ => node.version.to_string(),
+        }
+    }
+}
+
+impl Report for OutdatedReport {
+    fn text(&self) -> Vec<String> {
+        if self.nodes.is_empty() {
+            return vec![format!("✓ All {} nodes are up to date", self.checked)];
+        }
+        let mut lines: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                format!(
+                    "  {:<12}{}: {} ({}) {}",
+                    format!("{}:", node.state.as_str()),
+                    node.path.display(),
+                    node.name,
+                    node.node_type,
+                    Self::versions(node)
+                )
+            })
+            .collect();
+        lines.push(format!(
+            "{} of {} nodes are outdated, deprecated or unknown",
+            self.nodes.len(),
+            self.checked
+        ));
+        lines
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let rows = self
+            .nodes
+            .iter()
+            .map(|node| {
+                vec![
+                    node.state.as_str().to_string(),
+                    node.path.display().to_string(),
+                    node.name.clone(),
+                    node.node_type.clone(),
+                    node.version.to_string(),
+                    node.latest.map(|v| v.to_string()).unwrap_or_default(),
+                    node.behind.to_string(),
+                ]
+            })
+            .collect();
+        (
+            vec!["STATE", "PATH", "NODE", "TYPE", "VERSION", "LATEST", "BEHIND"],
+            rows,
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct NodesRefreshReport {
+    /// The source the versions were read from.
+    pub source: String,
+    pub nodes: usize,
+    pub status: CacheStatus,
+    /// The cache file, for versions read from GitHub.
+    pub cache: Option<String>,
+}
+
+impl Report for NodesRefreshReport {
+    fn text(&self) -> Vec<String> {
+        let what = match self.status {
+            CacheStatus::Revalidated => "unchanged since the last refresh",
+            CacheStatus::Stale => "GitHub unreachable, kept the cached copy",
+            CacheStatus::Fresh | CacheStatus::Fetched => "updated",
+        };
+        let mut lines = vec![format!(
+            "✓ {} node versions read from {} ({})",
+            self.nodes, self.source, what
+        )];
+        if let Some(cache) = &self.cache {
+            lines.push(format!("  cached in {}", cache));
+        }
+        lines
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        (
+            vec!["NODES", "SOURCE", "STATUS", "CACHE"],
+            vec![vec![
+                self.nodes.to_string(),
+                self.source.clone(),
+                self.status.as_str().to_string(),
+                self.cache.clone().unwrap_or_default(),
+            ]],
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct PushReport {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+}
+
+impl Report for PushReport {
+    fn text(&self) -> Vec<String> {
+        vec![format!("✓ Updated workflow {}: {}", self.id, self.name)]
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        (
+            vec!["ID", "NAME", "PATH"],
+            vec![vec![self.id.clone(), self.name.clone(), self.path.clone()]],
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct SyncReport {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub pushed: bool,
+    pub pulled: bool,
+    pub commit: Option<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl SyncReport {
+    pub fn new(outcome: SyncOutcome, path: &Path) -> Self {
+        Self {
+            id: outcome.id,
+            name: outcome.name,
+            path: path.display().to_string(),
+            pushed: outcome.pushed,
+            pulled: outcome.pulled,
+            commit: outcome.commit.map(|oid| oid.to_string()),
+            conflicts: outcome.conflicts,
+        }
+    }
+}
+
+/// Compact single-line rendering of a conflicting value.
+fn short_value(value: &Option<serde_json::Value>) -> String {
+    const MAX: usize = 60;
+    let text = match value {
+        Some(v) => v.to_string(),
+        None => "(deleted)".into(),
+    };
+    if text.chars().count() > MAX {
+        format!("{}…", text.chars().take(MAX).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// One-line description of a change, prefixed like a diff line.
+pub fn describe_change(change: &Change) -> String {
+    let prefix = match (&change.old, &change.new) {
+        (None, _) => '+',
+        (_, None) => '-',
+        _ => '~',
+    };
+    format!("{} {}", prefix, change_detail(change))
+}
+
+/// `path: old → new`, leaving out the side that does not exist.
+fn change_detail(change: &Change) -> String {
+    match (&change.old, &change.new) {
+        (None, new) => format!("{}: {}", change.path, short_value(new)),
+        (old, None) => format!("{}: {}", change.path, short_value(old)),
+        (old, new) => format!(
+            "{}: {} → {}",
+            change.path,
+            short_value(old),
+            short_value(new)
+        ),
+    }
+}
+
+impl Report for SyncReport {
+    fn text(&self) -> Vec<String> {
+        if !self.conflicts.is_empty() {
+            let mut lines = vec![format!(
+                "✗ Workflow {} was changed locally and remotely in {} place(s):",
+                self.id,
+                self.conflicts.len()
+            )];
+            for c in &self.conflicts {
+                lines.push(format!("  {}", c.path));
+                lines.push(format!("    local:  {}", short_value(&c.local)));
+                lines.push(format!("    remote: {}", short_value(&c.remote)));
+            }
+            return lines;
+        }
+        let summary = match (self.pushed, self.pulled) {
+            (true, true) => "merged local and remote changes",
+            (true, false) => "pushed local changes",
+            (false, true) => "pulled remote changes",
+            (false, false) => "already up to date",
+        };
+        vec![format!("✓ Workflow {} {}: {}", self.id, self.name, summary)]
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        if self.conflicts.is_empty() {
+            let yes_no = |b: bool| if b { "yes" } else { "no" }.to_string();
+            return (
+                vec!["ID", "NAME", "PUSHED", "PULLED"],
+                vec![vec![
+                    self.id.clone(),
+                    self.name.clone(),
+                    yes_no(self.pushed),
+                    yes_no(self.pulled),
+                ]],
+            );
+        }
+        let rows = self
+            .conflicts
+            .iter()
+            .map(|c| vec![c.path.clone(), short_value(&c.local), short_value(&c.remote)])
+            .collect();
+        (vec!["CONFLICT", "LOCAL", "REMOTE"], rows)
+    }
+}
+
+#[derive(Serialize)]
+pub struct DiffReport {
+    pub id: String,
+    pub path: String,
+    #[serde(flatten)]
+    pub diff: WorkflowDiff,
+}
+
+impl DiffReport {
+    /// One `(change, target, detail)` row per difference.
+    fn rows(&self) -> Vec<[String; 3]> {
+        let d = &self.diff;
+        let mut rows = Vec::new();
+        for n in &d.nodes_added {
+            let kind = n.node_type.as_ref().map(|t| format!("({})", t)).unwrap_or_default();
+            rows.push(["+ node".into(), n.name.clone(), kind]);
+        }
+        for n in &d.nodes_removed {
+            let kind = n.node_type.as_ref().map(|t| format!("({})", t)).unwrap_or_default();
+            rows.push(["- node".into(), n.name.clone(), kind]);
+        }
+        for r in &d.nodes_renamed {
+            rows.push(["~ rename".into(), r.from.clone(), format!("→ {}", r.to)]);
+        }
+        for n in &d.nodes_changed {
+            for c in &n.changes {
+                rows.push(["~ node".into(), n.node.clone(), change_detail(c)]);
+            }
+        }
+        for c in &d.connections_added {
+            rows.push(["+ connection".into(), c.to_string(), String::new()]);
+        }
+        for c in &d.connections_removed {
+            rows.push(["- connection".into(), c.to_string(), String::new()]);
+        }
+        for c in d.settings.iter().chain(&d.other) {
+            rows.push(["~ workflow".into(), String::new(), change_detail(c)]);
+        }
+        rows
+    }
+}
+
+impl Report for DiffReport {
+    fn text(&self) -> Vec<String> {
+        if self.diff.is_empty() {
+            return vec![format!("{} matches workflow {} on the server", self.path, self.id)];
+        }
+        let mut lines = vec![format!(
+            "Changes in {} compared to workflow {} on the server:",
+            self.path, self.id
+        )];
+        lines.extend(self.rows().into_iter().map(|[change, target, detail]| {
+            [change, target, detail]
+                .into_iter()
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        }));
+        lines.iter_mut().skip(1).for_each(|l| l.insert_str(0, "  "));
+        lines
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let rows = self.rows().into_iter().map(Vec::from).collect();
+        (vec!["CHANGE", "TARGET", "DETAIL"], rows)
+    }
+}
+
+#[derive(Serialize)]
+pub struct StatusReport {
+    pub workflows: Vec<WorkflowStatus>,
+}
+
+impl StatusReport {
+    fn location(wf: &WorkflowStatus) -> String {
+        match &wf.path {
+            Some(p) => p.display().to_string(),
+            None => format!("{} (ID {})", wf.name, wf.id),
+        }
+    }
+}
+
+impl Report for StatusReport {
+    fn text(&self) -> Vec<String> {
+        if self.workflows.is_empty() {
+            return vec!["No workflows found locally or on the server.".into()];
+        }
+        let mut lines = Vec::new();
+        let in_sync = self
+            .workflows
+            .iter()
+            .filter(|wf| wf.state == SyncState::InSync && !wf.uncommitted)
+            .count();
+        for wf in &self.workflows {
+            if wf.state == SyncState::InSync && !wf.uncommitted {
+                continue;
+            }
+            let uncommitted = if wf.uncommitted { " (uncommitted)" } else { "" };
+            lines.push(format!(
+                "  {:<20}{}{}",
+                format!("{}:", wf.state.as_str()),
+                Self::location(wf),
+                uncommitted
+            ));
+        }
+        lines.push(format!(
+            "{} of {} workflows in sync",
+            in_sync,
+            self.workflows.len()
+        ));
+        lines
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let rows = self
+            .workflows
+            .iter()
+            .map(|wf| {
+                vec![
+                    wf.state.as_str().to_string(),
+                    wf.id.clone(),
+                    wf.name.clone(),
+                    wf.path
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default(),
+                    if wf.uncommitted { "yes" } else { "" }.to_string(),
+                ]
+            })
+            .collect();
+        (vec!["STATE", "ID", "NAME", "PATH", "UNCOMMITTED"], rows)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: Option<String>,
+    pub source: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigReport {
+    pub path: String,
+    pub profile: String,
+    pub entries: Vec<ConfigEntry>,
+}
+
+impl Report for ConfigReport {
+    fn text(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Config file: {}", self.path),
+            format!("Profile: {}", self.profile),
+        ];
+        for entry in &self.entries {
+            let line = match (&entry.value, entry.source) {
+                (Some(v), Some(src)) => format!("  {} = {} ({})", entry.key, v, src),
+                (Some(v), None) => format!("  {} = {}", entry.key, v),
+                (None, _) => format!("  {} is not set", entry.key),
+            };
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let rows = self
+            .entries
+            .iter()
+            .map(|e| {
+                vec![
+                    e.key.clone(),
+                    e.value.clone().unwrap_or_default(),
+                    e.source.unwrap_or_default().to_string(),
+                ]
+            })
+            .collect();
+        (vec!["KEY", "VALUE", "SOURCE"], rows)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub host: Option<String>,
+    pub active: bool,
+}
+
+#[derive(Serialize)]
+pub struct ProfilesReport {
+    pub profiles: Vec<ProfileSummary>,
+}
+
+impl Report for ProfilesReport {
+    fn text(&self) -> Vec<String> {
+        self.profiles
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} {}{}",
+                    if p.active { "*" } else { " " },
+                    p.name,
+                    p.host
+                        .as_ref()
+                        .map(|h| format!(" ({})", h))
+                        .unwrap_or_default()
+                )
+            })
+            .collect()
+    }
+
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let rows = self
+            .profiles
+            .iter()
+            .map(|p| {
+                vec![
+                    p.name.clone(),
+                    p.host.clone().unwrap_or_default(),
+                    if p.active { "*" } else { "" }.to_string(),
+                ]
+            })
+            .collect();
+        (vec!["PROFILE", "HOST", "ACTIVE"], rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_columns_are_aligned() {
+        let rows = vec![
+            vec!["1".to_string(), "Short".to_string()],
+            vec!["1234".to_string(), "Longer name".to_string()],
+        ];
+        let table = render_table(&["ID", "NAME"], &rows);
+        assert_eq!(table, "ID    NAME\n1     Short\n1234  Longer name\n");
+    }
+
+    #[test]
+    fn list_report_uses_stable_field_names() {
+        let report = ListReport {
+            total: 1,
+            workflows: vec![WorkflowSummary {
+                id: "1".into(),
+                name: "Flow".into(),
+                active: true,
+                tags: vec!["prod".into()],
+                updated_at: Some("2024-01-01T00:00:00.000Z".into()),
+            }],
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["workflows"][0]["active"], true);
+        assert_eq!(json["workflows"][0]["tags"][0], "prod");
+        assert_eq!(json["workflows"][0]["updatedAt"], "2024-01-01T00:00:00.000Z");
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+/// How transient API failures are retried.
+///
+/// Delays grow exponentially from `base_delay` up to `max_delay`, with
+/// random jitter so concurrent clients do not retry in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 0).
+    ///
+    /// A server-provided `Retry-After` wins over the computed backoff. When
+    /// it is longer than `max_delay` there is no point in waiting, and
+    /// `None` is returned to give up instead.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(d) = retry_after {
+            return (d <= self.max_delay).then_some(d);
+        }
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        // Equal jitter: half the delay is fixed, the other half random.
+        let half = exp / 2;
+        Some(half + half.mul_f64(fastrand::f64()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+        };
+        let first = policy.delay(0, None).unwrap();
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+        let third = policy.delay(2, None).unwrap();
+        assert!(third >= Duration::from_millis(175) && third <= Duration::from_millis(350));
+        let tenth = policy.delay(9, None).unwrap();
+        assert!(tenth <= Duration::from_millis(350));
+    }
+
+    #[test]
+    fn retry_after_overrides_backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(7))),
+            Some(Duration::from_secs(7))
+        );
+    }
+
+    #[test]
+    fn oversized_retry_after_gives_up() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(86400))), None);
+        assert_eq!(
+            policy.delay(0, Some(policy.max_delay)),
+            Some(policy.max_delay)
+        );
+    }
+}
@@ -0,0 +1,379 @@
+use anyhow::Context;
+use argon2::Argon2;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, rand_core::RngCore};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::APP_NAME;
+
+/// Environment variable holding the passphrase for the encrypted-file store.
+pub const PASSPHRASE_ENV: &str = "N8N_SYNC_PASSPHRASE";
+
+/// Somewhere API keys can be kept outside of plain-text config files.
+///
+/// Secrets are stored per config profile.
+pub trait SecretStore {
+    fn get(&self, profile: &str) -> anyhow::Result<Option<String>>;
+    fn set(&self, profile: &str, secret: &str) -> anyhow::Result<()>;
+    fn delete(&self, profile: &str) -> anyhow::Result<()>;
+}
+
+/// The OS credential store: macOS Keychain, Windows Credential Manager or the
+/// Linux Secret Service.
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(profile: &str) -> keyring::Result<keyring::Entry> {
+        keyring::Entry::new(APP_NAME, profile)
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, profile: &str) -> anyhow::Result<Option<String>> {
+        match Self::entry(profile)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, profile: &str, secret: &str) -> anyhow::Result<()> {
+        Ok(Self::entry(profile)?.set_password(secret)?)
+    }
+
+    fn delete(&self, profile: &str) -> anyhow::Result<()> {
+        match Self::entry(profile)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct SecretsFile {
+    /// Salt for deriving the encryption key from the passphrase.
+    salt: String,
+    entries: BTreeMap<String, SealedSecret>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SealedSecret {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secrets encrypted with ChaCha20-Poly1305 under a passphrase-derived key,
+/// for machines without an OS credential store.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    passphrase: Option<String>,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: impl Into<PathBuf>, passphrase: Option<String>) -> Self {
+        Self {
+            path: path.into(),
+            passphrase,
+        }
+    }
+
+    /// Store next to the config file, unlocked with `N8N_SYNC_PASSPHRASE`.
+    pub fn from_env(config_path: &Path) -> Self {
+        Self::new(
+            config_path.with_file_name("secrets.json"),
+            env::var(PASSPHRASE_ENV).ok(),
+        )
+    }
+
+    fn cipher(&self, salt: &[u8]) -> anyhow::Result<ChaCha20Poly1305> {
+        let passphrase = self.passphrase.as_deref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "No OS keyring is available. Set {} to store the API key in an encrypted file instead",
+                PASSPHRASE_ENV
+            )
+        })?;
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {}", e))?;
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    fn read(&self) -> anyhow::Result<Option<SecretsFile>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(Some(serde_json::from_slice(&data).with_context(|| {
+            format!("Failed to parse {}", self.path.display())
+        })?))
+    }
+
+    fn write(&self, file: &SecretsFile) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec_pretty(file)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, profile: &str) -> anyhow::Result<Option<String>> {
+        let Some(file) = self.read()? else {
+            return Ok(None);
+        };
+        let Some(sealed) = file.entries.get(profile) else {
+            return Ok(None);
+        };
+        let cipher = self.cipher(&BASE64.decode(&file.salt)?)?;
+        let nonce = BASE64.decode(&sealed.nonce)?;
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                BASE64.decode(&sealed.ciphertext)?.as_slice(),
+            )
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Failed to decrypt {}. Is {} correct?",
+                    self.path.display(),
+                    PASSPHRASE_ENV
+                )
+            })?;
+        Ok(Some(String::from_utf8(plaintext)?))
+    }
+
+    fn set(&self, profile: &str, secret: &str) -> anyhow::Result<()> {
+        let mut file = match self.read()? {
+            Some(f) => f,
+            None => {
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                SecretsFile {
+                    salt: BASE64.encode(salt),
+                    entries: BTreeMap::new(),
+                }
+            }
+        };
+        let cipher = self.cipher(&BASE64.decode(&file.salt)?)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt secret"))?;
+        file.entries.insert(
+            profile.to_string(),
+            SealedSecret {
+                nonce: BASE64.encode(nonce),
+                ciphertext: BASE64.encode(ciphertext),
+            },
+        );
+        self.write(&file)
+    }
+
+    fn delete(&self, profile: &str) -> anyhow::Result<()> {
+        if let Some(mut file) = self.read()?
+            && file.entries.remove(profile).is_some()
+        {
+            self.write(&file)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prefers the OS keyring and falls back to the encrypted file when no
+/// credential store is reachable (e.g. headless Linux without a session bus).
+pub struct DefaultStore {
+    keyring: KeyringStore,
+    file: EncryptedFileStore,
+}
+
+impl DefaultStore {
+    pub fn new(file: EncryptedFileStore) -> Self {
+        Self {
+            keyring: KeyringStore,
+            file,
+        }
+    }
+
+    /// Store for the active config file location.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let config_path = crate::config::ConfigFile::path()?;
+        Ok(Self::new(EncryptedFileStore::from_env(&config_path)))
+    }
+}
+
+impl SecretStore for DefaultStore {
+    fn get(&self, profile: &str) -> anyhow::Result<Option<String>> {
+        match self.keyring.get(profile) {
+            Ok(Some(secret)) => Ok(Some(secret)),
+            Ok(None) => self.file.get(profile),
+            Err(e) => {
+                tracing::debug!(error = %e, "OS keyring unavailable, using encrypted file");
+                self.file.get(profile)
+            }
+        }
+    }
+
+    fn set(&self, profile: &str, secret: &str) -> anyhow::Result<()> {
+        match self.keyring.set(profile, secret) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                tracing::debug!(error = %e, "OS keyring unavailable, using encrypted file");
+                self.file.set(profile, secret)
+            }
+        }
+    }
+
+    fn delete(&self, profile: &str) -> anyhow::Result<()> {
+        let keyring = self.keyring.delete(profile);
+        self.file.delete(profile)?;
+        if let Err(e) = keyring {
+            tracing::debug!(error = %e, "OS keyring unavailable");
+        }
+        Ok(())
+    }
+}
+
+/// Read an API key from a file, Docker-secrets style, trimming whitespace.
+pub fn read_key_file(path: &Path) -> anyhow::Result<String> {
+    let key = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read API key file {}", path.display()))?
+        .trim()
+        .to_string();
+    if key.is_empty() {
+        return Err(anyhow::anyhow!("API key file {} is empty", path.display()));
+    }
+    Ok(key)
+}
+
+/// Run `cmd` through the shell and return its trimmed stdout as the API key.
+///
+/// The output is cached per command for the lifetime of the process, so
+/// secret managers are only asked once.
+pub fn run_key_command(cmd: &str) -> anyhow::Result<String> {
+    static CACHE: OnceLock<Mutex<BTreeMap<String, String>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(key) = cache.lock().unwrap().get(cmd) {
+        return Ok(key.clone());
+    }
+
+    #[cfg(windows)]
+    let output = Command::new("cmd").args(["/C", cmd]).output();
+    #[cfg(not(windows))]
+    let output = Command::new("sh").args(["-c", cmd]).output();
+    let output = output.with_context(|| format!("Failed to run `{}`", cmd))?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "`{}` exited with {}: {}",
+            cmd,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let key = String::from_utf8(output.stdout)?.trim().to_string();
+    if key.is_empty() {
+        return Err(anyhow::anyhow!("`{}` printed no API key", cmd));
+    }
+    cache.lock().unwrap().insert(cmd.to_string(), key.clone());
+    Ok(key)
+}
+
+/// In-memory store used by tests.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemoryStore(std::cell::RefCell<BTreeMap<String, String>>);
+
+#[cfg(test)]
+impl SecretStore for MemoryStore {
+    fn get(&self, profile: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.0.borrow().get(profile).cloned())
+    }
+
+    fn set(&self, profile: &str, secret: &str) -> anyhow::Result<()> {
+        self.0
+            .borrow_mut()
+            .insert(profile.to_string(), secret.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, profile: &str) -> anyhow::Result<()> {
+        self.0.borrow_mut().remove(profile);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_file_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+        let store = EncryptedFileStore::new(&path, Some("hunter2".into()));
+
+        assert_eq!(store.get("default").unwrap(), None);
+        store.set("default", "api-key-1").unwrap();
+        store.set("prod", "api-key-2").unwrap();
+        assert_eq!(store.get("default").unwrap().as_deref(), Some("api-key-1"));
+        assert_eq!(store.get("prod").unwrap().as_deref(), Some("api-key-2"));
+
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("api-key-1"));
+
+        let wrong = EncryptedFileStore::new(&path, Some("wrong".into()));
+        assert!(wrong.get("default").is_err());
+
+        store.delete("default").unwrap();
+        assert_eq!(store.get("default").unwrap(), None);
+    }
+
+    #[test]
+    fn encrypted_file_requires_passphrase_to_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EncryptedFileStore::new(dir.path().join("secrets.json"), None);
+        assert!(store.set("default", "key").is_err());
+    }
+
+    #[test]
+    fn key_file_is_trimmed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key");
+        fs::write(&path, "  secret-key\n").unwrap();
+        assert_eq!(read_key_file(&path).unwrap(), "secret-key");
+
+        fs::write(&path, "\n").unwrap();
+        assert!(read_key_file(&path).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn key_command_runs_once_per_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("count");
+        let cmd = format!("echo run >> {0}; echo cmd-key", counter.display());
+
+        assert_eq!(run_key_command(&cmd).unwrap(), "cmd-key");
+        assert_eq!(run_key_command(&cmd).unwrap(), "cmd-key");
+        let runs = fs::read_to_string(&counter).unwrap();
+        assert_eq!(runs.lines().count(), 1);
+
+        assert!(run_key_command("exit 3").is_err());
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::Context;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::api::{ListFilter, N8nClient, Workflow};
+use crate::git;
+use crate::layout::{self, NODES_DIR};
+use crate::lock::{LOCK_FILE, SyncLock};
+use crate::mirror::DEFAULT_CONCURRENCY;
+use crate::workflow::{
+    Format, WORKFLOW_FILE, WORKFLOW_YAML_FILE, is_workflow_file, sanitize_for_update,
+};
+
+/// How a local workflow file relates to its copy on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncState {
+    InSync,
+    LocalModified,
+    RemoteModified,
+    BothModified,
+    /// The file refers to a workflow that no longer exists on the server.
+    DeletedRemotely,
+    /// The workflow exists on the server but was never pulled here.
+    UntrackedRemotely,
+}
+
+impl SyncState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SyncState::InSync => "in sync",
+            SyncState::LocalModified => "local modified",
+            SyncState::RemoteModified => "remote modified",
+            SyncState::BothModified => "both modified",
+            SyncState::DeletedRemotely => "deleted remotely",
+            SyncState::UntrackedRemotely => "untracked remotely",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStatus {
+    pub id: String,
+    pub name: String,
+    /// The local file, `None` for workflows only found on the server.
+    pub path: Option<PathBuf>,
+    pub state: SyncState,
+    /// The file differs from the version committed at HEAD.
+    pub uncommitted: bool,
+}
+
+/// A workflow file found while scanning, with what we know about its base.
+struct LocalWorkflow {
+    path: PathBuf,
+    id: String,
+    json: Value,
+    /// `updatedAt` recorded when the file was last synced.
+    updated_at: Option<String>,
+    /// Content the file was last synced with: the lock entry if there is
+    /// one, else the committed version.
+    base: Option<Value>,
+    uncommitted: bool,
+}
+
+/// Recursively collect workflow files below `dir`: every `workflow.json` and
+/// `workflow.yaml` plus any other file recorded in a lock file. Hidden directories such as
+/// `.git` and the node files of expanded workflows are skipped.
+pub(crate) fn find_workflow_files(dir: &Path, out: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if path.is_dir() {
+            let node_files = path.ends_with(NODES_DIR)
+                && (dir.join(WORKFLOW_FILE).exists() || dir.join(WORKFLOW_YAML_FILE).exists());
+            if !hidden && !node_files && !path.ends_with("node_modules") {
+                find_workflow_files(&path, out)?;
+            }
+        } else if is_workflow_file(&path) {
+            out.push(path);
+        } else if path.ends_with(LOCK_FILE) {
+            let lock = SyncLock::load(dir)?;
+            out.extend(lock.workflows.keys().map(|f| dir.join(f)));
+        }
+    }
+    Ok(())
+}
+
+fn load_local(path: PathBuf) -> anyhow::Result<Option<LocalWorkflow>> {
+    let data = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let json = layout::load(&path)?;
+    let entry = crate::lock::entry_for(&path)?;
+    let head = git::head_content(&path)?;
+
+    let Some(id) = entry
+        .as_ref()
+        .map(|e| e.id.clone())
+        .or_else(|| json.get("id").and_then(Value::as_str).map(str::to_string))
+    else {
+        tracing::warn!(path = %path.display(), "skipping file without a workflow ID");
+        return Ok(None);
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let nodes = layout::nodes_dir(&path);
+    let committed = match head
+        .as_deref()
+        .and_then(|h| Format::from_path(&path).parse(h).ok())
+    {
+        // Expanded workflows are rebuilt from the committed node files.
+        Some(c) => layout::assemble(&c, nodes.strip_prefix(dir).unwrap_or(&nodes), |file| {
+            git::head_content(&dir.join(file))?
+                .ok_or_else(|| anyhow::anyhow!("{} is not committed", file.display()))
+        })
+        .ok(),
+        None => None,
+    };
+    // Node files of expanded workflows only show up in the assembled form.
+    let uncommitted = head.as_deref().is_some_and(|h| h != data.as_slice())
+        || (head.is_some() && committed.as_ref() != Some(&json));
+    Ok(Some(LocalWorkflow {
+        uncommitted,
+        updated_at: entry.as_ref().and_then(|e| e.updated_at.clone()),
+        base: entry
+            .map(|e| sanitize_for_update(&e.base))
+            .or_else(|| committed.map(|c| sanitize_for_update(&c))),
+        path,
+        id,
+        json,
+    }))
+}
+
+/// Compare every pulled workflow below `dir` with the server.
+///
+/// The server is listed once; a workflow's full content is only downloaded
+/// when its `updatedAt` differs from the one recorded at pull time (or when
+/// nothing was recorded), to tell real edits from metadata-only updates.
+pub async fn scan(client: &N8nClient, dir: &Path) -> anyhow::Result<Vec<WorkflowStatus>> {
+    let mut files = Vec::new();
+    find_workflow_files(dir, &mut files)?;
+    files.sort();
+    files.dedup();
+
+    let mut locals = Vec::new();
+    for path in files.into_iter().filter(|p| p.exists()) {
+        locals.extend(load_local(path)?);
+    }
+
+    let remote: HashMap<String, Workflow> = client
+        .list_workflows(&ListFilter::default(), crate::api::DEFAULT_PAGE_LIMIT)
+        .await
+        .with_context(|| "Failed to list workflows")?
+        .into_iter()
+        .map(|wf| (wf.id.clone(), wf))
+        .collect();
+
+    let mut statuses: Vec<WorkflowStatus> = stream::iter(locals)
+        .map(|local| classify(client, local, &remote))
+        .buffered(DEFAULT_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let tracked: HashSet<&str> = statuses.iter().map(|s| s.id.as_str()).collect();
+    let mut untracked: Vec<_> = remote
+        .values()
+        .filter(|wf| !tracked.contains(wf.id.as_str()))
+        .map(|wf| WorkflowStatus {
+            id: wf.id.clone(),
+            name: wf.name.clone(),
+            path: None,
+            state: SyncState::UntrackedRemotely,
+            uncommitted: false,
+        })
+        .collect();
+    untracked.sort_by(|a, b| a.name.cmp(&b.name));
+    statuses.extend(untracked);
+    Ok(statuses)
+}
+
+async fn classify(
+    client: &N8nClient,
+    local: LocalWorkflow,
+    remote: &HashMap<String, Workflow>,
+) -> anyhow::Result<WorkflowStatus> {
+    let name = local
+        .json
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let mut status = WorkflowStatus {
+        id: local.id.clone(),
+        name,
+        path: Some(local.path),
+        state: SyncState::InSync,
+        uncommitted: local.uncommitted,
+    };
+    let Some(listed) = remote.get(&local.id) else {
+        status.state = SyncState::DeletedRemotely;
+        return Ok(status);
+    };
+    status.name = listed.name.clone();
+
+    let body = sanitize_for_update(&local.json);
+    let unchanged_remotely = local.updated_at.is_some() && local.updated_at == listed.updated_at;
+    let remote_body = if unchanged_remotely {
+        None
+    } else {
+        let json = client
+            .get_workflow(&local.id)
+            .await
+            .with_context(|| format!("Failed to download workflow {}", local.id))?;
+        Some(sanitize_for_update(&json))
+    };
+
+    let (local_modified, remote_modified) = match (&local.base, &remote_body) {
+        (Some(base), remote) => (*base != body, remote.as_ref().is_some_and(|r| r != base)),
+        // Nothing to compare against: the sides either match or have diverged.
+        (None, remote) => {
+            let diverged = remote.as_ref() != Some(&body);
+            (diverged, diverged)
+        }
+    };
+    status.state = match (local_modified, remote_modified) {
+        (false, false) => SyncState::InSync,
+        (true, false) => SyncState::LocalModified,
+        (false, true) => SyncState::RemoteModified,
+        (true, true) => SyncState::BothModified,
+    };
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::N8nConfig;
+    use crate::lock::{self, LockEntry};
+    use serde_json::json;
+    use url::Url;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn workflow(id: &str, url: &str, updated_at: &str) -> Value {
+        json!({
+            "id": id,
+            "name": format!("Flow {}", id),
+            "nodes": [{"id": "a", "name": "A", "parameters": {"url": url}}],
+            "updatedAt": updated_at
+        })
+    }
+
+    /// Write a pulled copy of `pulled` to `dir/<id>/workflow.json`, then
+    /// replace its content with `local`.
+    fn checkout(dir: &Path, pulled: &Value, local: &Value) {
+        let id = pulled["id"].as_str().unwrap();
+        let file = dir.join(id).join(WORKFLOW_FILE);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        lock::record(&file, LockEntry::from_remote(id, pulled)).unwrap();
+        fs::write(&file, serde_json::to_vec_pretty(local).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn edited_node_files_are_uncommitted() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, _) = git::open_or_init(dir.path()).unwrap();
+        let file = dir.path().join("flow").join(WORKFLOW_FILE);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        let pulled = workflow("1", "x", "t1");
+        layout::save(&file, &pulled, Some(layout::Layout::Expanded)).unwrap();
+        let paths = layout::workflow_paths(Path::new("flow/workflow.json"));
+        let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+        git::commit_paths(&repo, &paths, "pull", None).unwrap();
+        assert!(!load_local(file.clone()).unwrap().unwrap().uncommitted);
+
+        layout::save(&file, &workflow("1", "edited", "t1"), None).unwrap();
+        assert_eq!(
+            git::head_content(&file).unwrap().unwrap(),
+            fs::read(&file).unwrap()
+        );
+        assert!(load_local(file).unwrap().unwrap().uncommitted);
+    }
+
+    #[tokio::test]
+    async fn classifies_each_workflow() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let pulled = |id| workflow(id, "x", "t1");
+        checkout(dir.path(), &pulled("1"), &pulled("1"));
+        checkout(dir.path(), &pulled("2"), &workflow("2", "local", "t1"));
+        checkout(dir.path(), &pulled("3"), &pulled("3"));
+        checkout(dir.path(), &pulled("4"), &workflow("4", "local", "t1"));
+        checkout(dir.path(), &pulled("5"), &pulled("5"));
+
+        let remote = [
+            pulled("1"),
+            pulled("2"),
+            workflow("3", "remote", "t2"),
+            workflow("4", "remote", "t2"),
+            workflow("6", "x", "t1"),
+        ];
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": remote })))
+            .mount(&server)
+            .await;
+        for wf in &remote {
+            Mock::given(method("GET"))
+                .and(path(format!(
+                    "/api/v1/workflows/{}",
+                    wf["id"].as_str().unwrap()
+                )))
+                .respond_with(ResponseTemplate::new(200).set_body_json(wf))
+                .mount(&server)
+                .await;
+        }
+        let client = N8nClient::new(N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        })
+        .unwrap();
+
+        let states: Vec<_> = scan(&client, dir.path())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.id, s.state))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                ("1".to_string(), SyncState::InSync),
+                ("2".to_string(), SyncState::LocalModified),
+                ("3".to_string(), SyncState::RemoteModified),
+                ("4".to_string(), SyncState::BothModified),
+                ("5".to_string(), SyncState::DeletedRemotely),
+                ("6".to_string(), SyncState::UntrackedRemotely),
+            ]
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use anyhow::Context;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::api::N8nClient;
+use crate::diff::{self, Change};
+use crate::git;
+use crate::layout;
+use crate::lock::{self, LockEntry};
+use crate::merge::{self, Conflict};
+use crate::workflow::sanitize_for_update;
+
+#[derive(Debug)]
+pub struct SyncOutcome {
+    pub id: String,
+    pub name: String,
+    /// Local changes were uploaded to the server.
+    pub pushed: bool,
+    /// Remote changes were written to the local file.
+    pub pulled: bool,
+    /// Changes made on both sides that need to be resolved by hand. When
+    /// non-empty nothing was written locally or remotely.
+    pub conflicts: Vec<Conflict>,
+    pub commit: Option<git2::Oid>,
+}
+
+/// Reconcile a local workflow file with its remote copy.
+///
+/// Both sides are merged against the revision recorded in the lock file by
+/// the last pull, push or sync. Non-conflicting changes are pushed to the
+/// server and written to `path`, the lock is updated and both files are
+/// committed if `path` lives in a git repository, with a message built from
+/// `message_template`.
+pub async fn sync_workflow(
+    client: &N8nClient,
+    path: &Path,
+    id: Option<&str>,
+    message_template: &str,
+) -> anyhow::Result<SyncOutcome> {
+    let local = layout::load(path)?;
+    let entry = lock::entry_for(path)?;
+
+    let id = lock::workflow_id(id, path, &local)?;
+    if entry.is_none() {
+        tracing::warn!(
+            path = %path.display(),
+            "no sync base recorded, every difference will be reported as a conflict"
+        );
+    }
+
+    let mut remote = client
+        .get_workflow(&id)
+        .await
+        .with_context(|| format!("Failed to download workflow {}", id))?;
+    let local_body = sanitize_for_update(&local);
+    let remote_body = sanitize_for_update(&remote);
+    let outcome = merge::three_way(entry.as_ref().map(|e| &e.base), &local_body, &remote_body);
+    let name = outcome
+        .merged
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    if !outcome.is_clean() {
+        return Ok(SyncOutcome {
+            id,
+            name,
+            pushed: false,
+            pulled: false,
+            conflicts: outcome.conflicts,
+            commit: None,
+        });
+    }
+
+    let pushed = outcome.merged != remote_body;
+    if pushed {
+        client
+            .update_workflow(&id, &outcome.merged)
+            .await
+            .with_context(|| format!("Failed to update workflow {}", id))?;
+        remote = client
+            .get_workflow(&id)
+            .await
+            .with_context(|| format!("Failed to download workflow {}", id))?;
+    }
+
+    let pulled = outcome.merged != local_body;
+    layout::save(path, &remote, None)?;
+    let mut files = layout::workflow_paths(path);
+    files.push(lock::record(path, LockEntry::from_remote(&id, &remote))?);
+    let files: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+    let commit = git::commit_files(
+        &files,
+        &git::commit_message(message_template, &remote),
+        Some(&remote),
+    )?;
+
+    Ok(SyncOutcome {
+        id,
+        name,
+        pushed,
+        pulled,
+        conflicts: Vec::new(),
+        commit,
+    })
+}
+
+/// Changes made on the server to workflow `id` since `entry` was recorded,
+/// i.e. what pushing a local copy would overwrite.
+///
+/// Empty when the remote `versionId` (or `updatedAt` on servers without
+/// versions) still matches the lock, or when the workflow moved on without
+/// its content changing, for example by being activated.
+pub async fn remote_changes_since(
+    client: &N8nClient,
+    id: &str,
+    entry: &LockEntry,
+) -> anyhow::Result<Vec<Change>> {
+    let remote = client
+        .get_workflow(id)
+        .await
+        .with_context(|| format!("Failed to download workflow {}", id))?;
+    let current = LockEntry::from_remote(id, &remote);
+    let moved = match (&entry.version_id, &current.version_id) {
+        (Some(recorded), Some(now)) => recorded != now,
+        _ => entry.updated_at != current.updated_at,
+    };
+    if !moved {
+        return Ok(Vec::new());
+    }
+    Ok(diff::changes(&entry.base, &current.base))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::N8nConfig;
+    use serde_json::json;
+    use std::fs;
+    use url::Url;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn node(id: &str, url: &str) -> Value {
+        json!({"id": id, "name": id.to_uppercase(), "parameters": {"url": url}})
+    }
+
+    fn workflow(a: &str, b: &str) -> Value {
+        json!({"id": "1", "name": "Flow", "nodes": [node("a", a), node("b", b)], "connections": {}})
+    }
+
+    async fn setup(remote: Value) -> (MockServer, N8nClient, tempfile::TempDir) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(remote))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        let client = N8nClient::new(N8nConfig {
+            api_key: "test-key".into(),
+            host: Url::parse(&server.uri()).unwrap(),
+        })
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let base = workflow("x", "y");
+        lock::record(
+            &dir.path().join("workflow.json"),
+            LockEntry::from_remote("1", &base),
+        )
+        .unwrap();
+        (server, client, dir)
+    }
+
+    #[tokio::test]
+    async fn merges_changes_from_both_sides() {
+        let (server, client, dir) = setup(workflow("x", "remote")).await;
+        let merged = workflow("local", "remote");
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/workflows/1"))
+            .and(body_json(sanitize_for_update(&merged)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&merged))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&merged))
+            .mount(&server)
+            .await;
+
+        let file = dir.path().join("workflow.json");
+        fs::write(&file, serde_json::to_vec(&workflow("local", "y")).unwrap()).unwrap();
+        let outcome = sync_workflow(&client, &file, None, git::DEFAULT_COMMIT_MESSAGE)
+            .await
+            .unwrap();
+
+        assert!(outcome.conflicts.is_empty());
+        assert!(outcome.pushed && outcome.pulled);
+        let written: Value = serde_json::from_slice(&fs::read(&file).unwrap()).unwrap();
+        assert_eq!(written, merged);
+        assert_eq!(
+            lock::entry_for(&file).unwrap().unwrap().base,
+            sanitize_for_update(&merged)
+        );
+    }
+
+    #[tokio::test]
+    async fn detects_remote_edits_made_after_pull() {
+        let mut remote = workflow("x", "edited");
+        remote["versionId"] = json!("v2");
+        let (_server, client, dir) = setup(remote).await;
+        let mut entry = lock::entry_for(&dir.path().join("workflow.json"))
+            .unwrap()
+            .unwrap();
+        entry.version_id = Some("v1".into());
+
+        let lost = remote_changes_since(&client, "1", &entry).await.unwrap();
+        assert_eq!(lost.len(), 1);
+        assert_eq!(lost[0].path, "nodes[\"B\"].parameters.url");
+        assert_eq!(lost[0].new, Some(json!("edited")));
+    }
+
+    #[tokio::test]
+    async fn unchanged_version_is_not_reported() {
+        let mut remote = workflow("x", "edited");
+        remote["versionId"] = json!("v1");
+        let (_server, client, dir) = setup(remote).await;
+        let mut entry = lock::entry_for(&dir.path().join("workflow.json"))
+            .unwrap()
+            .unwrap();
+        entry.version_id = Some("v1".into());
+
+        assert!(
+            remote_changes_since(&client, "1", &entry)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_on_conflicts_without_writing() {
+        let (server, client, dir) = setup(workflow("remote", "y")).await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let file = dir.path().join("workflow.json");
+        let local = serde_json::to_vec(&workflow("local", "y")).unwrap();
+        fs::write(&file, &local).unwrap();
+        let outcome = sync_workflow(&client, &file, Some("1"), git::DEFAULT_COMMIT_MESSAGE)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].path, "nodes[\"A\"].parameters.url");
+        assert_eq!(fs::read(&file).unwrap(), local);
+    }
+}
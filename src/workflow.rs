@@ -0,0 +1,333 @@
+use anyhow::Context;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name used for a pulled workflow inside its directory.
+pub const WORKFLOW_FILE: &str = "workflow.json";
+
+/// File name used for a workflow pulled in YAML format.
+pub const WORKFLOW_YAML_FILE: &str = "workflow.yaml";
+
+/// Added to the file stem of a workflow to name the sidecar that holds its
+/// volatile metadata, e.g. `workflow.meta.json` next to `workflow.json`.
+pub const METADATA_SUFFIX: &str = ".meta";
+
+/// File format workflows are stored in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Pretty-printed JSON, as exported by n8n
+    #[default]
+    Json,
+    /// YAML, with multi-line strings such as code written as block scalars
+    Yaml,
+}
+
+impl Format {
+    /// The format of the file at `path`, from its extension.
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Format::Yaml,
+            _ => Format::Json,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    /// File name of a workflow stored in this format inside its directory.
+    pub fn workflow_file(self) -> &'static str {
+        match self {
+            Format::Json => WORKFLOW_FILE,
+            Format::Yaml => WORKFLOW_YAML_FILE,
+        }
+    }
+
+    pub fn parse(self, data: &[u8]) -> anyhow::Result<Value> {
+        Ok(match self {
+            Format::Json => serde_json::from_slice(data)?,
+            Format::Yaml => serde_yaml::from_slice(data)?,
+        })
+    }
+
+    /// Serialize `json`, ending with a newline. YAML uses serde_yaml's
+    /// default styles: block scalars for multi-line strings only.
+    pub fn serialize(self, json: &Value) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Format::Json => {
+                let mut data = serde_json::to_vec_pretty(json)?;
+                data.push(b'\n');
+                data
+            }
+            Format::Yaml => serde_yaml::to_string(json)?.into_bytes(),
+        })
+    }
+}
+
+/// Whether `path` names a pulled workflow, i.e. `workflow.json` or
+/// `workflow.yaml`.
+pub fn is_workflow_file(path: &Path) -> bool {
+    path.ends_with(WORKFLOW_FILE) || path.ends_with(WORKFLOW_YAML_FILE)
+}
+
+/// Read and parse the workflow file at `path` in the format given by its
+/// extension.
+pub fn read(path: &Path) -> anyhow::Result<Value> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let format = Format::from_path(path);
+    format.parse(&data).with_context(|| {
+        format!(
+            "Failed to parse {} in {}",
+            format.extension().to_uppercase(),
+            path.display()
+        )
+    })
+}
+
+/// Top-level fields the server changes on every save or that describe the
+/// server rather than the workflow. They are kept out of the workflow file so
+/// its history only shows real edits.
+pub const VOLATILE_FIELDS: &[&str] = &[
+    "createdAt",
+    "updatedAt",
+    "versionId",
+    "triggerCount",
+    "shared",
+];
+
+/// Convert a workflow name into a filesystem-friendly slug
+pub fn slugify(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+/// Remove fields not accepted by the Public API when updating a workflow.
+///
+/// The result is in canonical form, so bodies compare equal whatever order
+/// the nodes were stored in.
+pub fn sanitize_for_update(json: &Value) -> Value {
+    let allowed = [
+        "name",
+        "nodes",
+        "connections",
+        "settings",
+        "staticData",
+    ];
+
+    let mut obj = Map::new();
+    for key in allowed.iter() {
+        if let Some(v) = json.get(*key) {
+            obj.insert((*key).to_string(), v.clone());
+        }
+    }
+    canonicalize(&Value::Object(obj))
+}
+
+/// Identity used to match a node across versions of a workflow: its `id`,
+/// falling back to its `name` for workflows exported without node IDs.
+pub(crate) fn node_key(node: &Value) -> Option<&str> {
+    node.get("id")
+        .or_else(|| node.get("name"))
+        .and_then(Value::as_str)
+}
+
+/// Path segment naming a node in merge and diff output, e.g. `nodes["Webhook"]`.
+pub(crate) fn node_label(node: &Value) -> String {
+    let name = node
+        .get("name")
+        .and_then(Value::as_str)
+        .or_else(|| node_key(node))
+        .unwrap_or_default();
+    format!("nodes[{:?}]", name)
+}
+
+pub(crate) fn find_node<'a>(nodes: Option<&'a [Value]>, key: Option<&str>) -> Option<&'a Value> {
+    key?;
+    nodes?.iter().find(|n| node_key(n) == key)
+}
+
+/// Recursively sort object keys and order `nodes` by name, so the same
+/// workflow always serializes to the same bytes.
+pub fn canonicalize(json: &Value) -> Value {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                Value::Object(entries.into_iter().map(|(k, v)| (k.clone(), sorted(v))).collect())
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+
+    let mut json = sorted(json);
+    if let Some(nodes) = json.get_mut("nodes").and_then(Value::as_array_mut) {
+        nodes.sort_by_key(|n| {
+            n.get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        });
+    }
+    json
+}
+
+/// Split a workflow as returned by the server into its content and the
+/// [`VOLATILE_FIELDS`] found in it.
+pub fn split_metadata(json: &Value) -> (Value, Value) {
+    let mut content = json.clone();
+    let mut metadata = Map::new();
+    if let Some(obj) = content.as_object_mut() {
+        for key in VOLATILE_FIELDS {
+            if let Some(v) = obj.remove(*key) {
+                metadata.insert((*key).to_string(), v);
+            }
+        }
+    }
+    (content, Value::Object(metadata))
+}
+
+/// Canonical on-disk form of a workflow: volatile metadata removed, keys
+/// sorted and nodes ordered by name, serialized in `format`.
+pub fn to_canonical(json: &Value, format: Format) -> anyhow::Result<Vec<u8>> {
+    let (content, _) = split_metadata(json);
+    format.serialize(&canonicalize(&content))
+}
+
+/// Path of the metadata sidecar for the workflow file at `path`, in the same
+/// format as the workflow.
+pub fn metadata_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(
+        "{}{}.{}",
+        stem,
+        METADATA_SUFFIX,
+        Format::from_path(path).extension()
+    ))
+}
+
+/// Whether `path` is a metadata sidecar rather than a workflow file.
+pub fn is_metadata_file(path: &Path) -> bool {
+    path.file_stem()
+        .is_some_and(|n| n.to_string_lossy().ends_with(METADATA_SUFFIX))
+}
+
+pub(crate) fn write_if_changed(path: &Path, data: &[u8]) -> anyhow::Result<bool> {
+    if fs::read(path).is_ok_and(|existing| existing == data) {
+        return Ok(false);
+    }
+    fs::write(path, data).with_context(|| format!("Failed to write to {}", path.display()))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn slugify_replaces_non_alphanumerics() {
+        assert_eq!(slugify("My New Flow!"), "my-new-flow");
+        assert_eq!(slugify("  CRM -> Slack  "), "crm----slack");
+    }
+
+    #[test]
+    fn sanitize_keeps_only_updatable_fields() {
+        let wf = json!({
+            "id": "1",
+            "name": "Flow",
+            "active": true,
+            "nodes": [],
+            "connections": {},
+            "settings": {},
+            "updatedAt": "2024-01-01T00:00:00.000Z"
+        });
+        assert_eq!(
+            sanitize_for_update(&wf),
+            json!({"name": "Flow", "nodes": [], "connections": {}, "settings": {}})
+        );
+    }
+
+    #[test]
+    fn canonical_json_is_stable_and_free_of_metadata() {
+        let wf = json!({
+            "name": "Flow",
+            "updatedAt": "2024-01-01T00:00:00.000Z",
+            "versionId": "abc",
+            "nodes": [
+                {"name": "Slack", "type": "n8n-nodes-base.slack", "id": "2"},
+                {"type": "n8n-nodes-base.webhook", "name": "Webhook", "id": "1"},
+                {"name": "HTTP", "parameters": {"url": "x", "method": "GET"}, "id": "3"}
+            ],
+            "connections": {}
+        });
+        let data = to_canonical(&wf, Format::Json).unwrap();
+        let text = String::from_utf8(data.clone()).unwrap();
+
+        assert!(!text.contains("updatedAt") && !text.contains("versionId"));
+        let written: Value = serde_json::from_slice(&data).unwrap();
+        let names: Vec<_> = written["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["HTTP", "Slack", "Webhook"]);
+        assert!(text.find("\"method\"").unwrap() < text.find("\"url\"").unwrap());
+        assert_eq!(to_canonical(&written, Format::Json).unwrap(), data);
+    }
+
+    #[test]
+    fn sidecar_paths() {
+        let path = Path::new("flows/billing.json");
+        assert_eq!(metadata_path(path), Path::new("flows/billing.meta.json"));
+        assert!(is_metadata_file(&metadata_path(path)) && !is_metadata_file(path));
+        assert_eq!(
+            metadata_path(Path::new(WORKFLOW_YAML_FILE)),
+            Path::new("workflow.meta.yaml")
+        );
+    }
+
+    #[test]
+    fn yaml_round_trips_to_identical_json() {
+        let wf = json!({
+            "name": "Flow",
+            "nodes": [{
+                "name": "Code",
+                "parameters": {
+                    "jsCode": "const a = 1;\n\nreturn [{ json: { a } }];\n",
+                    "notes": "trailing space \nand\ttabs",
+                    "expr": "={{ $json.value }}",
+                    "flags": ["yes", "no", "null", "1e3", "", "~"]
+                },
+                "position": [250.5, -40],
+                "typeVersion": 2
+            }],
+            "settings": {"executionOrder": "v1", "saveManualExecutions": true},
+            "staticData": null,
+            "pinData": {"Code": [{"json": {"big": 18446744073709551615u64, "f": 1.0}}]}
+        });
+        let yaml = to_canonical(&wf, Format::Yaml).unwrap();
+        let text = String::from_utf8(yaml.clone()).unwrap();
+        assert!(text.contains("jsCode: |\n"), "{}", text);
+
+        let back = Format::Yaml.parse(&yaml).unwrap();
+        assert_eq!(back, wf);
+        assert_eq!(
+            to_canonical(&back, Format::Json).unwrap(),
+            to_canonical(&wf, Format::Json).unwrap()
+        );
+    }
+}